@@ -0,0 +1,55 @@
+//! Compare the channel-backed `BufferWriter` against the previous `Mutex<String>` design when
+//! several tasks write concurrently.
+//!
+//! Run with `cargo bench --bench buffer_writer`.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lbtree::present::{BufferWriter, OutputWriter};
+
+const THREADS: usize = 4;
+const LINES_PER_THREAD: usize = 250_000;
+
+/// The original implementation, which takes a lock for every line
+struct MutexWriter {
+    buffer: Mutex<String>,
+}
+
+impl OutputWriter for MutexWriter {
+    fn write_line(&self, content: &str) {
+        let mut buf = self.buffer.lock().unwrap();
+        buf.push_str(content);
+        buf.push('\n');
+    }
+}
+
+fn run(writer: &dyn OutputWriter) -> Duration {
+    let start = Instant::now();
+    std::thread::scope(|s| {
+        for t in 0..THREADS {
+            s.spawn(move || {
+                for i in 0..LINES_PER_THREAD {
+                    writer.write_line(&format!("  -> Target id=10.0.{t}.{i} port=80"));
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn main() {
+    let mutex_writer = MutexWriter {
+        buffer: Mutex::new(String::new()),
+    };
+    let mutex_elapsed = run(&mutex_writer);
+
+    let buffer_writer = BufferWriter::new();
+    let buffer_elapsed = run(&buffer_writer);
+    let output = buffer_writer.get_output();
+    assert_eq!(output.lines().count(), THREADS * LINES_PER_THREAD);
+
+    println!("{THREADS} threads x {LINES_PER_THREAD} lines");
+    println!("  Mutex<String>: {mutex_elapsed:?}");
+    println!("  BufferWriter:  {buffer_elapsed:?}");
+}
@@ -0,0 +1,92 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::present::Present;
+
+/// Format a duration with its two largest units, e.g. `2h13m` or `45s`
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes, seconds) =
+        (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{days}d{hours}h")
+    } else if hours > 0 {
+        format!("{hours}h{minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Seconds between a timestamp, in seconds since the epoch, and `now`, or 0 for timestamps in the
+/// future
+pub fn secs_since(secs: i64, now: SystemTime) -> u64 {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    (now - secs).max(0) as u64
+}
+
+/// A resource shown with how long ago it was created, e.g. ` age=3d4h`, for `--age`
+///
+/// Nothing is added for resources whose API does not return a creation time.
+#[derive(Debug)]
+pub struct Aged<T> {
+    pub inner: T,
+    /// Creation time in seconds since the epoch
+    pub created: Option<i64>,
+    pub now: SystemTime,
+}
+
+impl<T: Present> Present for Aged<T> {
+    fn content(&self) -> String {
+        match self.created {
+            Some(created) => format!(
+                "{} age={}",
+                self.inner.content(),
+                format_duration(secs_since(created, self.now))
+            ),
+            None => self.inner.content(),
+        }
+    }
+
+    fn arn(&self) -> Option<String> {
+        self.inner.arn()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::present::AccessDenied;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(60), "1m0s");
+        assert_eq!(format_duration(3 * 86_400 + 5 * 3_600 + 7), "3d5h");
+    }
+
+    #[test]
+    fn test_aged_content() {
+        let now = UNIX_EPOCH + Duration::from_secs(100_000);
+        let aged = Aged {
+            inner: AccessDenied,
+            created: Some(100_000 - 7_980),
+            now,
+        };
+        assert_eq!(aged.content(), "<access denied> age=2h13m");
+
+        let unknown = Aged {
+            inner: AccessDenied,
+            created: None,
+            now,
+        };
+        assert_eq!(unknown.content(), "<access denied>");
+
+        // clock skew never shows a negative age
+        assert_eq!(secs_since(100_010, now), 0);
+    }
+}
@@ -1,15 +1,33 @@
-use aws_config::SdkConfig;
+use aws_config::{Region, SdkConfig};
+use aws_sdk_elasticloadbalancingv2::error::ProvideErrorMetadata;
 use aws_sdk_elasticloadbalancingv2::types::{
-    Action, ActionTypeEnum, Listener, LoadBalancer, Rule, TargetGroup, TargetHealthDescription,
+    Action, ActionTypeEnum, IpAddressType, Listener, LoadBalancer, LoadBalancerAttribute,
+    LoadBalancerTypeEnum, Rule, RuleCondition, TargetGroup, TargetGroupAttribute, TargetHealth,
+    TargetHealthDescription, TargetHealthStateEnum,
 };
 use color_eyre::eyre::{self, Context};
-use crossbeam::channel::unbounded;
+use crossbeam::channel::bounded;
 use skim::prelude::*;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::task::JoinHandle;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{Instrument, info_span, instrument};
 
-use crate::present::{OutputWriter, Present};
+use crate::age::Aged;
+use crate::error::{
+    Cancelled, LbtreeError, NotFound, NothingSelected, is_access_denied, is_access_denied_report,
+};
+use crate::estimate::Estimate;
+use crate::model::{self, AlbTree, ListenerNode, LoadBalancerNode, TargetGroupNode};
+use crate::pagination::collect_all;
+use crate::picker::{PICKER_CHANNEL_CAPACITY, send_to_picker};
+use crate::present::{AccessDenied, OutputWriter, Present, Queued};
+use crate::progress::Spinner;
+use crate::resolve::{arn_has_suffix, unique_match};
+use crate::tree::{Node, build_tree};
 
 #[derive(Debug, Clone)]
 struct LoadBalancerItem {
@@ -27,302 +45,3249 @@ impl SkimItem for LoadBalancerItem {
     }
 }
 
-impl Present for LoadBalancer {
-    fn content(&self) -> String {
-        format!(
-            "Load balancer ({dns_name})",
-            dns_name = self.dns_name().unwrap()
-        )
+/// Options controlling what is fetched and shown for a load balancer
+#[derive(Debug, Clone)]
+pub struct AlbOptions {
+    /// Highlight the rule and action that would serve this request
+    pub test_request: Option<TestRequest>,
+    /// Fetch and show load balancer and target group attributes (one extra API call for the load
+    /// balancer and each target group)
+    pub attributes: bool,
+    /// Fetch and show the WAF web ACL associated with the load balancer
+    pub waf: bool,
+    /// Order of listeners, target groups and targets, or the order AWS returns them if `None`
+    pub sort: Option<SortKey>,
+    /// Show a placeholder under listeners without rules and target groups without targets
+    pub show_empty: bool,
+    /// Number of times the health of a target group's targets is fetched again after being
+    /// throttled, on top of the SDK's own retries
+    pub health_retries: u32,
+    /// Only show target groups with a target that is not healthy, those targets, and the rules
+    /// that forward to them
+    pub only_unhealthy: bool,
+    /// Stop listing load balancers for the picker or `--name` after this many
+    pub max_items: Option<usize>,
+    /// Fetch and show the tags of listener rules (one extra API call per 20 rules)
+    pub tags: bool,
+    /// Show how long ago the load balancer was created
+    pub age: bool,
+}
+
+/// Default for [`AlbOptions::health_retries`] and `--health-retries`
+pub const DEFAULT_HEALTH_RETRIES: u32 = 3;
+
+impl Default for AlbOptions {
+    fn default() -> Self {
+        Self {
+            test_request: None,
+            attributes: false,
+            waf: false,
+            sort: None,
+            show_empty: false,
+            health_retries: DEFAULT_HEALTH_RETRIES,
+            only_unhealthy: false,
+            max_items: None,
+            tags: false,
+            age: false,
+        }
     }
+}
+
+/// How target groups and targets are ordered, e.g. from `--sort`
+///
+/// Listeners are always ordered by port, so output is the same from one run to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Target groups by name and targets by id
+    Name,
+    /// Target groups and targets by port
+    Port,
+    /// Unhealthy targets first
+    Health,
+}
+
+fn sort_listeners(listeners: &mut [Listener]) {
+    listeners.sort_by_key(|listener| listener.port());
+}
 
-    fn indent(&self) -> usize {
-        0
+fn sort_target_groups(target_groups: &mut [TargetGroup], sort: Option<SortKey>) {
+    match sort {
+        Some(SortKey::Name) => {
+            target_groups.sort_by(|a, b| a.target_group_name().cmp(&b.target_group_name()))
+        }
+        Some(SortKey::Port) => target_groups.sort_by_key(|tg| tg.port()),
+        Some(SortKey::Health) | None => {}
     }
 }
 
-impl Present for Listener {
-    fn content(&self) -> String {
-        format!(
-            "Listener protocol={protocol} port={port}",
-            protocol = self.protocol().unwrap(),
-            port = self.port().unwrap(),
-        )
+fn sort_targets(targets: &mut [TargetHealthDescription], sort: Option<SortKey>) {
+    fn id(target: &TargetHealthDescription) -> Option<&str> {
+        target.target().and_then(|t| t.id())
     }
 
-    fn indent(&self) -> usize {
-        2
+    match sort {
+        Some(SortKey::Name) => targets.sort_by(|a, b| id(a).cmp(&id(b))),
+        Some(SortKey::Port) => targets.sort_by_key(|t| t.target().and_then(|t| t.port())),
+        // stable, so targets in the same state keep their order
+        Some(SortKey::Health) => targets.sort_by_key(|t| {
+            t.target_health().and_then(|h| h.state()) == Some(&TargetHealthStateEnum::Healthy)
+        }),
+        None => {}
     }
 }
 
-impl Present for Rule {
-    fn content(&self) -> String {
-        format!(
-            "Rule priority={priority} is-default={is_default}",
-            priority = self.priority().unwrap(),
-            is_default = self.is_default().unwrap(),
-        )
+/// A request to simulate against the listener rules, e.g. from `--test-request`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRequest {
+    pub host: String,
+    pub path: String,
+    pub method: String,
+}
+
+impl TestRequest {
+    /// Build a test request from a URL such as `https://api.example.com/admin?debug=1`
+    ///
+    /// The port, query string and fragment are dropped as they are not used by the conditions we
+    /// evaluate.
+    pub fn parse(url: &str, method: &str) -> eyre::Result<Self> {
+        let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+        let (authority, path) = match without_scheme.find(['/', '?', '#']) {
+            Some(idx) => without_scheme.split_at(idx),
+            None => (without_scheme, "/"),
+        };
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let path = if path.is_empty() { "/" } else { path };
+
+        let host = authority
+            .rsplit_once('@')
+            .map(|(_, host)| host)
+            .unwrap_or(authority);
+        let host = match host.rsplit_once(':') {
+            Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+            _ => host,
+        };
+        if host.is_empty() {
+            eyre::bail!("no host found in test request URL {url:?}");
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            path: path.to_string(),
+            method: method.to_ascii_uppercase(),
+        })
+    }
+}
+
+/// Settings of a load balancer from `describe_load_balancer_attributes`
+#[derive(Debug, Clone, Default)]
+pub struct LoadBalancerAttributes {
+    pub deletion_protection: Option<String>,
+    /// Only set for application load balancers
+    pub idle_timeout: Option<String>,
+    /// Only set for network and gateway load balancers, application load balancers configure
+    /// cross-zone load balancing per target group
+    pub cross_zone: Option<String>,
+    /// Taken from the load balancer itself rather than its attributes
+    pub ip_address_type: Option<String>,
+    pub access_logs: AccessLogs,
+}
+
+/// Where a load balancer writes its access logs
+#[derive(Debug, Clone, Default)]
+pub struct AccessLogs {
+    pub enabled: Option<String>,
+    pub bucket: Option<String>,
+    pub prefix: Option<String>,
+}
+
+impl LoadBalancerAttributes {
+    pub fn from_attributes(attributes: &[LoadBalancerAttribute]) -> Self {
+        let mut out = Self::default();
+        for attribute in attributes {
+            // unset values, such as the bucket when logging is disabled, are returned as ""
+            let value = attribute
+                .value()
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string());
+            match attribute.key() {
+                Some("deletion_protection.enabled") => out.deletion_protection = value,
+                Some("idle_timeout.timeout_seconds") => out.idle_timeout = value,
+                Some("load_balancing.cross_zone.enabled") => out.cross_zone = value,
+                Some("access_logs.s3.enabled") => out.access_logs.enabled = value,
+                Some("access_logs.s3.bucket") => out.access_logs.bucket = value,
+                Some("access_logs.s3.prefix") => out.access_logs.prefix = value,
+                _ => {}
+            }
+        }
+        out
     }
+}
 
-    fn indent(&self) -> usize {
-        4
+impl Present for LoadBalancerAttributes {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Attributes deletion-protection={}",
+            self.deletion_protection.as_deref().unwrap_or("unknown")
+        );
+        if let Some(idle_timeout) = &self.idle_timeout {
+            content.push_str(&format!(" idle-timeout={idle_timeout}s"));
+        }
+        if let Some(cross_zone) = &self.cross_zone {
+            content.push_str(&format!(" cross-zone={cross_zone}"));
+        }
+        if let Some(ip_address_type) = &self.ip_address_type {
+            content.push_str(&format!(" ip-address-type={ip_address_type}"));
+        }
+        content
     }
 }
 
-impl Present for Action {
+impl Present for AccessLogs {
     fn content(&self) -> String {
-        match self.r#type().unwrap() {
-            ActionTypeEnum::AuthenticateCognito => todo!("authenticate cognito"),
-            ActionTypeEnum::AuthenticateOidc => todo!(),
-            ActionTypeEnum::FixedResponse => {
-                let cfg = self.fixed_response_config().unwrap();
-                format!(
-                    "Action (fixed-repsonse) msg={msg:?} status-code={status_code:?}",
-                    msg = cfg.message_body(),
-                    status_code = cfg.status_code(),
-                )
-            }
-            ActionTypeEnum::Forward => {
-                let _fwd = self.forward_config().unwrap();
-                "Action (forward)".to_string()
+        let mut content = format!(
+            "Access logs enabled={}",
+            self.enabled.as_deref().unwrap_or("unknown")
+        );
+        if let Some(bucket) = &self.bucket {
+            content.push_str(&format!(" bucket={bucket}"));
+        }
+        if let Some(prefix) = &self.prefix {
+            content.push_str(&format!(" prefix={prefix}"));
+        }
+        content
+    }
+}
+
+/// Connection draining, stickiness and slow start settings of a target group
+#[derive(Debug, Clone, Default)]
+pub struct TargetGroupAttributes {
+    pub deregistration_delay: Option<String>,
+    pub stickiness_enabled: Option<String>,
+    pub stickiness_type: Option<String>,
+    pub slow_start: Option<String>,
+    /// `true`, `false` or `use_load_balancer_configuration`
+    pub cross_zone: Option<String>,
+}
+
+impl TargetGroupAttributes {
+    pub fn from_attributes(attributes: &[TargetGroupAttribute]) -> Self {
+        let mut out = Self::default();
+        for attribute in attributes {
+            let value = attribute.value().map(|v| v.to_string());
+            match attribute.key() {
+                Some("deregistration_delay.timeout_seconds") => out.deregistration_delay = value,
+                Some("stickiness.enabled") => out.stickiness_enabled = value,
+                Some("stickiness.type") => out.stickiness_type = value,
+                Some("slow_start.duration_seconds") => out.slow_start = value,
+                Some("load_balancing.cross_zone.enabled") => out.cross_zone = value,
+                _ => {}
             }
-            ActionTypeEnum::Redirect => todo!(),
-            _ => todo!(),
         }
+        out
     }
+}
 
-    fn indent(&self) -> usize {
-        6
+impl Present for TargetGroupAttributes {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Attributes deregistration-delay={deregistration_delay}s stickiness={stickiness} stickiness-type={stickiness_type} slow-start={slow_start}s",
+            deregistration_delay = self.deregistration_delay.as_deref().unwrap_or("?"),
+            stickiness = self.stickiness_enabled.as_deref().unwrap_or("unknown"),
+            stickiness_type = self.stickiness_type.as_deref().unwrap_or("unknown"),
+            slow_start = self.slow_start.as_deref().unwrap_or("?"),
+        );
+        if let Some(cross_zone) = &self.cross_zone {
+            content.push_str(&format!(" cross-zone={cross_zone}"));
+        }
+        content
     }
 }
 
-impl Present for TargetGroup {
+/// How a target group checks the health of its targets, shown with `--attributes`
+///
+/// The matcher holds the HTTP codes, or gRPC codes for gRPC target groups, that count as a
+/// passing check, which is a common cause of targets that work but are reported unhealthy.
+#[derive(Debug, Clone)]
+pub struct HealthCheckSettings(pub TargetGroup);
+
+impl Present for HealthCheckSettings {
     fn content(&self) -> String {
+        let tg = &self.0;
+        if tg.health_check_enabled() == Some(false) {
+            return "Health check disabled".to_string();
+        }
+        let path_str = tg
+            .health_check_path()
+            .map(|path| format!(" path={path}"))
+            .unwrap_or_default();
+        let matcher_str = match tg.matcher() {
+            Some(matcher) if tg.protocol_version() == Some("GRPC") => matcher
+                .grpc_code()
+                .map(|codes| format!(" grpc-codes={codes}")),
+            Some(matcher) => matcher
+                .http_code()
+                .map(|codes| format!(" http-codes={codes}")),
+            None => None,
+        }
+        .unwrap_or_default();
+        let seconds = |value: Option<i32>| value.map_or("?".to_string(), |v| format!("{v}s"));
+        let count = |value: Option<i32>| value.map_or("?".to_string(), |v| v.to_string());
+
         format!(
-            "Target group \"{name}\" protocol={protocol} port={port}",
-            name = self.target_group_name().unwrap_or("??"),
-            protocol = self.protocol().unwrap(),
-            port = self.port().unwrap()
+            "Health check protocol={protocol} port={port}{path_str}{matcher_str} interval={interval} timeout={timeout} healthy-threshold={healthy} unhealthy-threshold={unhealthy}",
+            protocol = tg
+                .health_check_protocol()
+                .map(|p| p.as_str())
+                .unwrap_or("unknown"),
+            port = tg.health_check_port().unwrap_or("unknown"),
+            interval = seconds(tg.health_check_interval_seconds()),
+            timeout = seconds(tg.health_check_timeout_seconds()),
+            healthy = count(tg.healthy_threshold_count()),
+            unhealthy = count(tg.unhealthy_threshold_count()),
         )
     }
-
-    fn indent(&self) -> usize {
-        2
-    }
 }
 
-impl Present for TargetHealthDescription {
+/// A load balancer that routes to a target group, shown under the target group by
+/// `lbtree target-group`
+#[derive(Debug, Clone)]
+pub struct AttachedLoadBalancer(pub LoadBalancer);
+
+impl Present for AttachedLoadBalancer {
     fn content(&self) -> String {
-        let target = self.target().unwrap();
+        let lb = &self.0;
         format!(
-            "Target id={} port={}",
-            target.id().unwrap(),
-            target.port().unwrap()
+            "Used by load balancer \"{name}\" type={lb_type} scheme={scheme} state={state}",
+            name = lb.load_balancer_name().unwrap_or("unknown"),
+            lb_type = lb.r#type().map(|t| t.as_str()).unwrap_or("unknown"),
+            scheme = lb.scheme().map(|s| s.as_str()).unwrap_or("unknown"),
+            state = lb
+                .state()
+                .and_then(|state| state.code())
+                .map(|code| code.as_str())
+                .unwrap_or("unknown"),
         )
     }
 
-    fn indent(&self) -> usize {
-        4
+    fn arn(&self) -> Option<String> {
+        self.0.load_balancer_arn().map(|arn| arn.to_string())
     }
 }
 
-/// Let the user choose the load balancer to use
-async fn select_load_balancer(
-    client: &aws_sdk_elasticloadbalancingv2::Client,
-) -> eyre::Result<Option<String>> {
-    // Create crossbeam channel for streaming items to skim
-    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+/// The WAF web ACL protecting a load balancer, if any
+#[derive(Debug, Clone)]
+pub struct WafAssociation {
+    pub web_acl_name: Option<String>,
+}
 
-    // Clone client for background task
-    let client = client.clone();
+impl Present for WafAssociation {
+    fn content(&self) -> String {
+        match &self.web_acl_name {
+            Some(name) => format!("WAF web-acl=\"{name}\""),
+            None => "WAF web-acl=none".to_string(),
+        }
+    }
+}
 
-    // Spawn background task to fetch and stream load balancers
-    let fetch_handle = tokio::spawn(async move {
-        let result: eyre::Result<()> = async {
-            // Use paginator to stream results as they arrive
-            let mut paginator = client.describe_load_balancers().into_paginator().send();
+/// Marker for a target group whose targets could not be fetched, e.g. because the call was still
+/// throttled after retrying
+#[derive(Debug, Clone, Copy)]
+pub struct HealthUnavailable(pub &'static str);
 
-            // Stream each page as it arrives from AWS
-            while let Some(page) = paginator.next().await {
-                let page = page.context("fetching load balancers page")?;
+impl Present for HealthUnavailable {
+    fn content(&self) -> String {
+        format!("<health unavailable: {}>", self.0)
+    }
+}
 
-                // Send each LB to skim immediately
-                for lb in page.load_balancers() {
-                    let name = lb.load_balancer_name().unwrap_or("unknown");
-                    let dns = lb.dns_name().unwrap_or("unknown");
-                    let arn = lb.load_balancer_arn().unwrap_or("");
+/// Placeholder for a list that was fetched but is empty, e.g. `<no targets>`, so it can be told
+/// apart from one that was not fetched
+#[derive(Debug, Clone, Copy)]
+pub struct Empty(pub &'static str);
 
-                    let item = LoadBalancerItem {
-                        display: format!("{} ({})", name, dns),
-                        arn: arn.to_string(),
-                    };
+impl Present for Empty {
+    fn content(&self) -> String {
+        format!("<no {}>", self.0)
+    }
+}
 
-                    // Send to skim (crossbeam send is fast)
-                    // Ignore send errors - means user closed skim early
-                    let _ = tx.send(Arc::new(item));
-                }
-            }
+/// Static address of a network load balancer in one availability zone
+#[derive(Debug, Clone)]
+pub struct StaticAddress {
+    pub zone: String,
+    pub ip_address: Option<String>,
+    pub private_ip_address: Option<String>,
+    /// Only set for dualstack load balancers
+    pub ipv6_address: Option<String>,
+    pub allocation_id: Option<String>,
+}
 
-            Ok(())
+impl StaticAddress {
+    /// Addresses of a network load balancer; other load balancer types do not have any
+    pub fn from_load_balancer(lb: &LoadBalancer) -> Vec<Self> {
+        if lb.r#type() != Some(&LoadBalancerTypeEnum::Network) {
+            return Vec::new();
         }
-        .await;
 
-        // Drop sender to signal EOF to skim
-        drop(tx);
+        lb.availability_zones()
+            .iter()
+            .flat_map(|az| {
+                az.load_balancer_addresses()
+                    .iter()
+                    .map(|address| StaticAddress {
+                        zone: az.zone_name().unwrap_or("unknown").to_string(),
+                        ip_address: address.ip_address().map(|ip| ip.to_string()),
+                        private_ip_address: address.private_ipv4_address().map(|ip| ip.to_string()),
+                        ipv6_address: address.i_pv6_address().map(|ip| ip.to_string()),
+                        allocation_id: address.allocation_id().map(|id| id.to_string()),
+                    })
+            })
+            .collect()
+    }
+}
 
-        result
-    });
+impl Present for StaticAddress {
+    fn content(&self) -> String {
+        let mut content = format!("Address az={}", self.zone);
+        if let Some(ip_address) = &self.ip_address {
+            content.push_str(&format!(" ip={ip_address}"));
+        }
+        if let Some(private_ip_address) = &self.private_ip_address {
+            content.push_str(&format!(" private-ip={private_ip_address}"));
+        }
+        if let Some(ipv6_address) = &self.ipv6_address {
+            content.push_str(&format!(" ipv6={ipv6_address}"));
+        }
+        if let Some(allocation_id) = &self.allocation_id {
+            content.push_str(&format!(" allocation={allocation_id}"));
+        }
+        content
+    }
+}
 
-    // Configure skim options
-    let options = SkimOptionsBuilder::default()
-        .height("50%".to_string())
-        .prompt("Select load balancer: ".to_string())
-        .build()
-        .map_err(|e| eyre::eyre!("building skim options: {}", e))?;
+/// Wraps a rule or action that would serve the request passed with `--test-request`
+#[derive(Debug)]
+struct Matched<T>(T);
 
-    // Start skim UI immediately (receives items as they arrive)
-    let selected = Skim::run_with(&options, Some(rx));
+impl<T: Present> Present for Matched<T> {
+    fn content(&self) -> String {
+        format!("{} <== matches request", self.0.content())
+    }
 
-    // Wait for background task and check for errors
-    let fetch_result = fetch_handle
-        .await
-        .context("background fetch task panicked")?;
+    fn arn(&self) -> Option<String> {
+        self.0.arn()
+    }
+}
 
-    // Propagate any AWS API errors
-    fetch_result?;
+/// Target group with a count of its healthy targets, so the health is visible even when the
+/// targets themselves are hidden by `--depth`
+#[derive(Debug, Clone)]
+pub struct TargetGroupHealth {
+    pub target_group: TargetGroup,
+    pub healthy: usize,
+    pub total: usize,
+}
 
-    // Extract selection
-    let selected = match selected {
-        Some(output) => {
-            if output.is_abort {
-                return Ok(None);
-            }
+impl TargetGroupHealth {
+    pub fn new(target_group: TargetGroup, targets: &[TargetHealthDescription]) -> Self {
+        let healthy = targets
+            .iter()
+            .filter(|t| {
+                t.target_health().and_then(|h| h.state()) == Some(&TargetHealthStateEnum::Healthy)
+            })
+            .count();
+        Self {
+            target_group,
+            healthy,
+            total: targets.len(),
+        }
+    }
+}
 
-            output
-                .selected_items
-                .first()
-                .map(|item| item.output().to_string())
+impl Present for TargetGroupHealth {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "{} ({}/{} healthy)",
+            self.target_group.content(),
+            self.healthy,
+            self.total
+        );
+        // requests routed to this target group fail
+        if self.healthy == 0 {
+            content.push_str(" <== no healthy targets");
         }
-        None => None,
-    };
+        content
+    }
 
-    Ok(selected)
+    fn arn(&self) -> Option<String> {
+        self.target_group.arn()
+    }
 }
 
-/// Display an Application Load Balancer hierarchy
-pub async fn display_alb(
-    config: &SdkConfig,
-    arn: Option<String>,
-    writer: &dyn OutputWriter,
-) -> eyre::Result<()> {
-    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+/// How the targets of a target group are spread across availability zones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneDistribution {
+    /// Number of targets in each health state, e.g. `healthy` or `draining`, keyed by zone
+    pub zones: BTreeMap<String, BTreeMap<String, usize>>,
+}
 
-    let lb_arn = if let Some(arn) = arn {
-        arn
-    } else {
-        match select_load_balancer(&client).await? {
-            Some(arn) => arn,
-            None => {
-                eprintln!("No load balancer selected");
-                std::process::exit(1);
-            }
+impl ZoneDistribution {
+    /// Count targets by the zone they are registered in, returning `None` unless they are in more
+    /// than one zone
+    pub fn from_targets(targets: &[TargetHealthDescription]) -> Option<Self> {
+        let mut zones: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+        for target in targets {
+            let Some(zone) = target.target().and_then(|t| t.availability_zone()) else {
+                continue;
+            };
+            let state = target
+                .target_health()
+                .and_then(|h| h.state())
+                .map_or("unknown", |state| state.as_str());
+            *zones
+                .entry(zone.to_string())
+                .or_default()
+                .entry(state.to_string())
+                .or_default() += 1;
         }
-    };
+        (zones.len() > 1).then_some(Self { zones })
+    }
+}
 
-    let load_balancer = client
-        .describe_load_balancers()
-        .load_balancer_arns(&lb_arn)
-        .send()
-        .await
-        .context("describing load balancer")?;
-    // panic safety: the client will return a 404 if the listener cannot be found, so we expect at
-    // least one result
-    let lb = &load_balancer.load_balancers()[0];
-    lb.present(writer);
+impl Present for ZoneDistribution {
+    fn content(&self) -> String {
+        let zones: Vec<String> = self
+            .zones
+            .iter()
+            .map(|(zone, states)| {
+                // healthy first, then the other states by name, e.g. `draining` during a deploy
+                let healthy = states.get_key_value("healthy");
+                let others = states.iter().filter(|(state, _)| *state != "healthy");
+                let counts: Vec<String> = healthy
+                    .into_iter()
+                    .chain(others)
+                    .map(|(state, count)| format!("{count} {state}"))
+                    .collect();
+                format!("{zone}: {}", counts.join(" "))
+            })
+            .collect();
+        format!("Zones {}", zones.join(", "))
+    }
+}
 
-    // parallel fetch of the results
+/// Evaluate a listener's rules in priority order and return the rule and action that would serve
+/// the request
+///
+/// Only `host-header`, `path-pattern` and `http-request-method` conditions can be evaluated, so a
+/// rule with any other condition is never considered a match.
+pub fn match_request<'a>(
+    rules: &'a [Rule],
+    host: &str,
+    path: &str,
+    method: &str,
+) -> Option<(&'a Rule, &'a Action)> {
+    let mut ordered: Vec<&Rule> = rules.iter().collect();
+    ordered.sort_by_key(|rule| evaluation_order(rule));
 
-    let listeners_client = client.clone();
-    let listeners_lb_arn = lb_arn.clone();
-    let listeners_fut: JoinHandle<eyre::Result<Vec<Box<dyn Present>>>> = tokio::spawn(async move {
-        let mut out: Vec<Box<dyn Present>> = Vec::new();
+    ordered
+        .into_iter()
+        .filter(|rule| {
+            rule.conditions()
+                .iter()
+                .all(|condition| condition_matches(condition, host, path, method))
+        })
+        .find_map(|rule| routing_action(rule).map(|action| (rule, action)))
+}
 
-        let listeners = listeners_client
-            .describe_listeners()
-            .load_balancer_arn(listeners_lb_arn)
-            .send()
-            .await
-            .wrap_err("describing listeners for load balancer")?;
+/// Position of a rule in the order a listener evaluates them
+fn evaluation_order(rule: &Rule) -> u32 {
+    // the default rule has the priority "default" and is always evaluated last
+    rule.priority()
+        .and_then(|p| p.parse::<u32>().ok())
+        .unwrap_or(u32::MAX)
+}
+
+/// Actions of a rule in the order they are performed
+pub(crate) fn ordered_actions(rule: &Rule) -> Vec<&Action> {
+    let mut actions: Vec<&Action> = rule.actions().iter().collect();
+    actions.sort_by_key(|action| action.order().unwrap_or(i32::MAX));
+    actions
+}
 
-        for listener in listeners.listeners() {
-            out.push(Box::new(listener.clone()));
+/// The action that routes the request, skipping any authentication actions that run first
+fn routing_action(rule: &Rule) -> Option<&Action> {
+    ordered_actions(rule).into_iter().find(|action| {
+        !matches!(
+            action.r#type(),
+            Some(ActionTypeEnum::AuthenticateCognito | ActionTypeEnum::AuthenticateOidc)
+        )
+    })
+}
 
-            let listener_arn = if let Some(arn) = listener.listener_arn() {
-                arn
-            } else {
-                continue;
-            };
+fn condition_matches(condition: &RuleCondition, host: &str, path: &str, method: &str) -> bool {
+    match condition.field() {
+        Some("host-header") => {
+            let host = host.to_ascii_lowercase();
+            condition_values(
+                condition.host_header_config().map(|c| c.values()),
+                condition,
+            )
+            .iter()
+            .any(|pattern| wildcard_match(&pattern.to_ascii_lowercase(), &host))
+        }
+        Some("path-pattern") => condition_values(
+            condition.path_pattern_config().map(|c| c.values()),
+            condition,
+        )
+        .iter()
+        .any(|pattern| wildcard_match(pattern, path)),
+        Some("http-request-method") => condition_values(
+            condition.http_request_method_config().map(|c| c.values()),
+            condition,
+        )
+        .iter()
+        .any(|m| m == method),
+        _ => false,
+    }
+}
 
-            // - rules
-            let rules = listeners_client
-                .describe_rules()
-                .listener_arn(listener_arn)
-                .send()
-                .await
-                .context("describing rules for listener")?;
+/// Values of a condition, falling back to the legacy `Values` field which is used instead of the
+/// typed config by older clients
+fn condition_values<'a>(
+    config_values: Option<&'a [String]>,
+    condition: &'a RuleCondition,
+) -> &'a [String] {
+    match config_values {
+        Some(values) if !values.is_empty() => values,
+        _ => condition.values(),
+    }
+}
 
-            for rule in rules.rules() {
-                out.push(Box::new(rule.clone()));
+/// Match `input` against an ALB condition pattern, where `*` matches any number of characters and
+/// `?` matches exactly one
+fn wildcard_match(pattern: &str, input: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let input: Vec<char> = input.chars().collect();
 
-                for action in rule.actions() {
-                    out.push(Box::new(action.clone()));
-                }
-            }
+    let (mut p, mut i) = (0, 0);
+    // position of the last `*` seen, and the input position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < input.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == input[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, i));
+            p += 1;
+        } else if let Some((star_p, star_i)) = star {
+            // let the last `*` absorb one more character and retry
+            p = star_p + 1;
+            i = star_i + 1;
+            star = Some((star_p, star_i + 1));
+        } else {
+            return false;
         }
+    }
 
-        Ok(out)
-    });
-    let target_groups_client = client.clone();
-    let target_groups_lb_arn = lb_arn.clone();
-    let target_groups_fut: JoinHandle<eyre::Result<Vec<Box<dyn Present>>>> =
-        tokio::spawn(async move {
-            let mut out: Vec<Box<dyn Present>> = Vec::new();
-            let target_groups = target_groups_client
-                .describe_target_groups()
-                .load_balancer_arn(target_groups_lb_arn)
-                .send()
-                .await
-                .context("describing target groups")?;
+    pattern[p..].iter().all(|&c| c == '*')
+}
 
-            for target_group in target_groups.target_groups() {
-                out.push(Box::new(target_group.clone()));
+/// Maximum number of listeners whose rules are fetched at the same time
+const MAX_CONCURRENT_RULE_FETCHES: usize = 8;
 
-                let tg_arn = if let Some(arn) = target_group.target_group_arn() {
-                    arn
-                } else {
-                    continue;
-                };
+/// Maximum number of resources `describe_tags` accepts in one call
+const MAX_TAGGED_RESOURCES_PER_CALL: usize = 20;
 
-                // - targets
-                let targets = target_groups_client
-                    .describe_target_health()
-                    .target_group_arn(tg_arn)
-                    .send()
-                    .await
-                    .wrap_err("describing targets in target group")?;
+/// Wait before fetching target health again after it was throttled, doubled on every retry
+const INITIAL_HEALTH_RETRY_DELAY: Duration = Duration::from_millis(200);
 
-                for target in targets.target_health_descriptions() {
-                    out.push(Box::new(target.clone()));
-                }
-            }
-            Ok(out)
-        });
+/// Maximum number of regions whose load balancers are fetched at the same time with `--region all`
+const MAX_CONCURRENT_REGION_FETCHES: usize = 4;
+
+/// Number of profiles whose load balancers are fetched at once by `--all-profiles`
+const MAX_CONCURRENT_PROFILE_FETCHES: usize = 4;
+
+/// Maximum number of availability zones listed on the load balancer line
+const MAX_DISPLAYED_AZS: usize = 3;
 
-    for presenter in listeners_fut.await?? {
-        presenter.present(writer);
+impl Present for LoadBalancer {
+    fn content(&self) -> String {
+        let mut zones: Vec<String> = self
+            .availability_zones()
+            .iter()
+            .take(MAX_DISPLAYED_AZS)
+            .map(|az| {
+                format!(
+                    "{}/{}",
+                    az.zone_name().unwrap_or("unknown"),
+                    az.subnet_id().unwrap_or("unknown")
+                )
+            })
+            .collect();
+        let hidden = self
+            .availability_zones()
+            .len()
+            .saturating_sub(MAX_DISPLAYED_AZS);
+        if hidden > 0 {
+            zones.push(format!("+{hidden} more"));
+        }
+
+        // only labelled when the load balancer also serves IPv6, e.g. `dualstack`
+        let ip_address_type_str = self
+            .ip_address_type()
+            .filter(|t| **t != IpAddressType::Ipv4)
+            .map(|t| format!(" ip-address-type={}", t.as_str()))
+            .unwrap_or_default();
+
+        format!(
+            "Load balancer ({dns_name}) scheme={scheme}{ip_address_type_str} sgs=[{sgs}] vpc={vpc_id} azs=[{zones}]",
+            dns_name = self.dns_name().unwrap_or("unknown"),
+            scheme = self.scheme().map(|s| s.as_str()).unwrap_or("unknown"),
+            sgs = self.security_groups().join(", "),
+            vpc_id = self.vpc_id().unwrap_or("unknown"),
+            zones = zones.join(", "),
+        )
     }
-    for presenter in target_groups_fut.await?? {
-        presenter.present(writer);
+
+    fn arn(&self) -> Option<String> {
+        self.load_balancer_arn().map(|arn| arn.to_string())
     }
+}
 
-    Ok(())
+impl Present for Listener {
+    /// Describe the listener, with the ALPN policy of TLS listeners on network load balancers and
+    /// the mutual TLS mode of HTTPS listeners when it is not `off`
+    fn content(&self) -> String {
+        let alpn = match self.alpn_policy() {
+            [] => String::new(),
+            policies => format!(" alpn={}", policies.join(",")),
+        };
+        let mtls = self
+            .mutual_authentication()
+            .filter(|m| m.mode().is_some_and(|mode| mode != "off"))
+            .map(|m| {
+                let trust_store = m
+                    .trust_store_arn()
+                    .map(|arn| format!(" trust-store={arn}"))
+                    .unwrap_or_default();
+                format!(" mtls={}{trust_store}", m.mode().unwrap_or("unknown"))
+            })
+            .unwrap_or_default();
+        format!(
+            "Listener protocol={protocol} port={port}{alpn}{mtls}",
+            protocol = self.protocol().map(|p| p.as_str()).unwrap_or("unknown"),
+            port = self
+                .port()
+                .map(|p| p.to_string())
+                .unwrap_or("unknown".to_string()),
+        )
+    }
+
+    fn arn(&self) -> Option<String> {
+        self.listener_arn().map(|arn| arn.to_string())
+    }
+}
+
+/// Tags of a listener rule, such as the team that owns it or the ticket that changed it
+#[derive(Debug, Clone)]
+pub struct RuleTags(pub Vec<(String, String)>);
+
+impl Present for RuleTags {
+    fn content(&self) -> String {
+        let tags: Vec<String> = self
+            .0
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        format!("Tags {}", tags.join(" "))
+    }
+}
+
+impl Present for Rule {
+    fn content(&self) -> String {
+        format!(
+            "Rule priority={priority} is-default={is_default}",
+            priority = self.priority().unwrap_or("unknown"),
+            is_default = self
+                .is_default()
+                .map(|d| d.to_string())
+                .unwrap_or("unknown".to_string()),
+        )
+    }
+
+    fn arn(&self) -> Option<String> {
+        self.rule_arn().map(|arn| arn.to_string())
+    }
+}
+
+impl Present for Action {
+    /// Describe the action
+    ///
+    /// Authenticate actions only show the identity provider and client id. The OIDC client secret
+    /// is never formatted, so it cannot leak into any output format.
+    fn content(&self) -> String {
+        match self.r#type() {
+            Some(ActionTypeEnum::FixedResponse) => match self.fixed_response_config() {
+                Some(cfg) => format!(
+                    "Action (fixed-repsonse) msg={msg:?} status-code={status_code:?}",
+                    msg = cfg.message_body(),
+                    status_code = cfg.status_code(),
+                ),
+                None => "Action (fixed-repsonse)".to_string(),
+            },
+            Some(ActionTypeEnum::Forward) => format!(
+                "Action (forward) target-groups=[{}]",
+                forward_target_group_arns(self).join(", ")
+            ),
+            Some(ActionTypeEnum::Redirect) => match self.redirect_config() {
+                // unset parts of the redirect keep the value from the original request
+                Some(cfg) => format!(
+                    "Action (redirect) location={protocol}://{host}:{port}{path}{query} status-code={status_code}",
+                    protocol = cfg.protocol().unwrap_or("#{protocol}"),
+                    host = cfg.host().unwrap_or("#{host}"),
+                    port = cfg.port().unwrap_or("#{port}"),
+                    path = cfg.path().unwrap_or("/#{path}"),
+                    query = cfg
+                        .query()
+                        .filter(|q| !q.is_empty())
+                        .map(|q| format!("?{q}"))
+                        .unwrap_or_default(),
+                    status_code = cfg.status_code().map(|c| c.as_str()).unwrap_or("unknown"),
+                ),
+                None => "Action (redirect)".to_string(),
+            },
+            Some(ActionTypeEnum::AuthenticateOidc) => match self.authenticate_oidc_config() {
+                // client_secret is deliberately not shown
+                Some(cfg) => format!(
+                    "Action (authenticate-oidc) issuer={issuer} client-id={client_id}",
+                    issuer = cfg.issuer().unwrap_or("unknown"),
+                    client_id = cfg.client_id().unwrap_or("unknown"),
+                ),
+                None => "Action (authenticate-oidc)".to_string(),
+            },
+            Some(ActionTypeEnum::AuthenticateCognito) => match self.authenticate_cognito_config() {
+                Some(cfg) => format!(
+                    "Action (authenticate-cognito) user-pool={user_pool} client-id={client_id}",
+                    user_pool = cfg.user_pool_arn().unwrap_or("unknown"),
+                    client_id = cfg.user_pool_client_id().unwrap_or("unknown"),
+                ),
+                None => "Action (authenticate-cognito)".to_string(),
+            },
+            Some(other) => format!("Action ({})", other.as_str()),
+            None => "Action (unknown)".to_string(),
+        }
+    }
+}
+
+/// ARNs of the target groups a forward action sends requests to
+///
+/// Actions created with the older API only set `TargetGroupArn` rather than a forward config.
+pub(crate) fn forward_target_group_arns(action: &Action) -> Vec<&str> {
+    let weighted: Vec<&str> = action
+        .forward_config()
+        .map(|cfg| cfg.target_groups())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tg| tg.target_group_arn())
+        .collect();
+    if weighted.is_empty() {
+        action.target_group_arn().into_iter().collect()
+    } else {
+        weighted
+    }
+}
+
+/// Action of a rule, with the names of the load balancer's target groups keyed by ARN
+#[derive(Debug, Clone)]
+pub struct ActionInfo {
+    pub action: Action,
+    pub target_group_names: Arc<HashMap<String, String>>,
+    /// Whether the rule has other actions, in which case the order of this one is shown, e.g.
+    /// `Action #1 (authenticate-oidc)`
+    pub show_order: bool,
+}
+
+impl Present for ActionInfo {
+    fn content(&self) -> String {
+        let content = self.content_without_order();
+        match self.action.order().filter(|_| self.show_order) {
+            Some(order) => content.replacen("Action ", &format!("Action #{order} "), 1),
+            None => content,
+        }
+    }
+}
+
+impl ActionInfo {
+    fn content_without_order(&self) -> String {
+        if self.action.r#type() != Some(&ActionTypeEnum::Forward) {
+            return self.action.content();
+        }
+
+        let target_groups: Vec<&str> = forward_target_group_arns(&self.action)
+            .into_iter()
+            .map(|arn| {
+                self.target_group_names
+                    .get(arn)
+                    .map(|name| name.as_str())
+                    .unwrap_or(arn)
+            })
+            .collect();
+        format!(
+            "Action (forward) target-groups=[{}]",
+            target_groups.join(", ")
+        )
+    }
+}
+
+/// Target groups a listener can send traffic to through any of its rules, including the default
+/// one, named where the name is known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerRoutes {
+    /// In the order the rules forwarding to them are evaluated, without duplicates
+    pub target_groups: Vec<String>,
+}
+
+impl ListenerRoutes {
+    /// Follow the forward actions of the rules, returning `None` if none forwards anywhere, e.g.
+    /// for a listener that only redirects to HTTPS
+    pub fn from_rules(
+        rules: &[Rule],
+        target_group_names: &HashMap<String, String>,
+    ) -> Option<Self> {
+        let mut ordered: Vec<&Rule> = rules.iter().collect();
+        ordered.sort_by_key(|rule| evaluation_order(rule));
+        let mut target_groups: Vec<String> = Vec::new();
+        for arn in ordered
+            .into_iter()
+            .flat_map(|rule| rule.actions())
+            .flat_map(forward_target_group_arns)
+        {
+            let name = target_group_names
+                .get(arn)
+                .map(|n| n.as_str())
+                .unwrap_or(arn);
+            if !target_groups.iter().any(|tg| tg == name) {
+                target_groups.push(name.to_string());
+            }
+        }
+        (!target_groups.is_empty()).then_some(Self { target_groups })
+    }
+}
+
+impl Present for ListenerRoutes {
+    fn content(&self) -> String {
+        format!(
+            "Reachable target-groups=[{}]",
+            self.target_groups.join(", ")
+        )
+    }
+}
+
+impl Present for TargetGroup {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Target group \"{name}\"",
+            name = self.target_group_name().unwrap_or("??"),
+        );
+        // what the ids of the targets are: instance ids, IP addresses, or Lambda or ALB ARNs
+        if let Some(target_type) = self.target_type() {
+            content.push_str(&format!(" type={}", target_type.as_str()));
+        }
+        // Lambda target groups have neither a protocol nor a port
+        if let Some(protocol) = self.protocol() {
+            content.push_str(&format!(" protocol={}", protocol.as_str()));
+        }
+        if let Some(port) = self.port() {
+            content.push_str(&format!(" port={port}"));
+        }
+        content
+    }
+
+    fn arn(&self) -> Option<String> {
+        self.target_group_arn().map(|arn| arn.to_string())
+    }
+}
+
+/// Describe the health of a target, e.g. ` state=unhealthy reason=Target.Timeout`
+///
+/// Targets that are still registering or are being deregistered during a deploy are labelled as
+/// such, so they are not mistaken for failing ones.
+fn target_state(health: &TargetHealth) -> String {
+    let Some(state) = health.state() else {
+        return String::new();
+    };
+    let mut content = match state {
+        TargetHealthStateEnum::Initial => " state=initial (registering)".to_string(),
+        TargetHealthStateEnum::Draining => " state=draining (deregistering)".to_string(),
+        other => format!(" state={}", other.as_str()),
+    };
+    if *state != TargetHealthStateEnum::Healthy {
+        if let Some(reason) = health.reason() {
+            content.push_str(&format!(" reason={}", reason.as_str()));
+        }
+    }
+    // e.g. "Target deregistration is in progress"
+    if *state == TargetHealthStateEnum::Draining {
+        if let Some(description) = health.description() {
+            content.push_str(&format!(" detail={description:?}"));
+        }
+    }
+    content
+}
+
+/// Why an unhealthy target is failing its health checks, with a hint at what to check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheckFailure {
+    /// Reason code, e.g. `Target.Timeout`
+    pub reason: String,
+    pub description: Option<String>,
+}
+
+impl HealthCheckFailure {
+    /// `None` unless the target is unhealthy or unavailable, as the other states have their
+    /// reason on the target line already
+    pub fn from_health(health: &TargetHealth) -> Option<Self> {
+        match health.state()? {
+            TargetHealthStateEnum::Unhealthy | TargetHealthStateEnum::Unavailable => {}
+            _ => return None,
+        }
+        Some(HealthCheckFailure {
+            reason: health.reason()?.as_str().to_string(),
+            description: health.description().map(|d| d.to_string()),
+        })
+    }
+
+    /// What the reason code means and what to check, for the codes that can be acted on
+    fn explanation(&self) -> Option<(&'static str, &'static str)> {
+        let explanation = match self.reason.as_str() {
+            "Target.ResponseCodeMismatch" => (
+                "health check got an unexpected status code",
+                "check the health check path exists and the matcher covers the codes it returns",
+            ),
+            "Target.Timeout" => (
+                "health check request timed out",
+                "check the target's security group allows the load balancer on the health check port, and the health check path responds within the timeout",
+            ),
+            "Target.FailedHealthChecks" => (
+                "target failed health checks",
+                "check the application is listening on the health check port and protocol",
+            ),
+            "Target.NotRegistered" => (
+                "target is not registered with the target group",
+                "register the target, or check the service that registers it",
+            ),
+            "Target.NotInUse" => (
+                "target group is not used by any load balancer, or the target is in a zone the load balancer is not enabled for",
+                "attach the target group to a listener rule or enable the target's zone",
+            ),
+            "Target.InvalidState" => (
+                "target is stopped or terminated",
+                "start the instance or deregister it",
+            ),
+            "Target.IpUnusable" => (
+                "target IP address is in use by a load balancer",
+                "register a different IP address",
+            ),
+            "Target.HealthCheckDisabled" => (
+                "health checks are disabled",
+                "enable health checks on the target group to get a health state",
+            ),
+            "Elb.InternalError" => (
+                "health checks failed due to an internal load balancer error",
+                "retry later, and contact AWS support if it persists",
+            ),
+            _ => return None,
+        };
+        Some(explanation)
+    }
+}
+
+impl Present for HealthCheckFailure {
+    fn content(&self) -> String {
+        let mut content = format!("Health check failure {}", self.reason);
+        if let Some((meaning, _)) = self.explanation() {
+            content.push_str(&format!(": {meaning}"));
+        }
+        if let Some(description) = &self.description {
+            content.push_str(&format!(" detail={description:?}"));
+        }
+        if let Some((_, hint)) = self.explanation() {
+            content.push_str(&format!(" hint=\"{hint}\""));
+        }
+        content
+    }
+}
+
+impl Present for TargetHealthDescription {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Target id={}",
+            self.target().and_then(|t| t.id()).unwrap_or("unknown")
+        );
+        // Lambda targets do not have a port
+        if let Some(port) = self.target().and_then(|t| t.port()) {
+            content.push_str(&format!(" port={port}"));
+        }
+        if let Some(health) = self.target_health() {
+            content.push_str(&target_state(health));
+        }
+        content
+    }
+
+    fn arn(&self) -> Option<String> {
+        // instance id, IP address or Lambda function ARN depending on the target type
+        self.target().and_then(|t| t.id()).map(|id| id.to_string())
+    }
+}
+
+/// Let the user choose the load balancer to use, from at most `max_items` of them
+async fn select_load_balancer(
+    client: &aws_sdk_elasticloadbalancingv2::Client,
+    max_items: Option<usize>,
+) -> eyre::Result<Option<String>> {
+    // Create crossbeam channel for streaming items to skim
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = bounded(PICKER_CHANNEL_CAPACITY);
+
+    // Clone client for background task
+    let client = client.clone();
+
+    // Spawn background task to fetch and stream load balancers
+    let fetch_handle = tokio::spawn(async move {
+        // the number of load balancers left out, if listing stopped at max_items
+        let result: eyre::Result<Option<usize>> = async {
+            // Use paginator to stream results as they arrive
+            let mut paginator = client.describe_load_balancers().into_paginator().send();
+            let mut listed = 0;
+
+            // Stream each page as it arrives from AWS
+            while let Some(page) = paginator
+                .next()
+                .instrument(info_span!("describe_load_balancers"))
+                .await
+            {
+                let page = page.context("fetching load balancers page")?;
+
+                // Send each LB to skim immediately
+                for (i, lb) in page.load_balancers().iter().enumerate() {
+                    if max_items.is_some_and(|max| listed >= max) {
+                        // the rest of this page, and maybe more pages
+                        return Ok(Some(page.load_balancers().len() - i));
+                    }
+                    listed += 1;
+                    let name = lb.load_balancer_name().unwrap_or("unknown");
+                    let dns = lb.dns_name().unwrap_or("unknown");
+                    let arn = lb.load_balancer_arn().unwrap_or("");
+
+                    let item = LoadBalancerItem {
+                        display: format!("{} ({})", name, dns),
+                        arn: arn.to_string(),
+                    };
+
+                    // Send to skim, stopping if the user already closed it
+                    if !send_to_picker(&tx, Arc::new(item)).await {
+                        return Ok(None);
+                    }
+                }
+            }
+
+            Ok(None)
+        }
+        .await;
+
+        // Drop sender to signal EOF to skim
+        drop(tx);
+
+        result
+    });
+
+    // Configure skim options
+    let options = SkimOptionsBuilder::default()
+        .height("50%".to_string())
+        .prompt("Select load balancer: ".to_string())
+        .build()
+        .map_err(|e| eyre::eyre!("building skim options: {}", e))?;
+
+    // Start skim UI immediately (receives items as they arrive)
+    let selected = Skim::run_with(&options, Some(rx));
+
+    // Wait for background task and check for errors
+    let spinner = Spinner::new("Fetching load balancers");
+    let fetch_result = fetch_handle
+        .await
+        .context("background fetch task panicked")?;
+    drop(spinner);
+
+    // Propagate any AWS API errors
+    if let Some(more) = fetch_result? {
+        eprintln!("... (truncated, {more}+ more load balancers not listed)");
+    }
+
+    // Extract selection
+    let selected = match selected {
+        Some(output) => {
+            if output.is_abort {
+                return Err(Cancelled.into());
+            }
+
+            output
+                .selected_items
+                .first()
+                .map(|item| item.output().to_string())
+        }
+        None => None,
+    };
+
+    Ok(selected)
+}
+
+/// Listeners with the rules of each, in the same order, and whether the credentials were denied
+/// access to them
+type ListenersWithRules = (Vec<Listener>, Vec<Option<Vec<Rule>>>, Vec<bool>);
+
+/// Fetch every rule of a listener, following the `NextMarker` of each page
+async fn describe_all_rules(
+    client: &aws_sdk_elasticloadbalancingv2::Client,
+    listener_arn: &str,
+) -> eyre::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    let mut marker = None;
+    loop {
+        let page = client
+            .describe_rules()
+            .listener_arn(listener_arn)
+            .set_marker(marker)
+            .send()
+            .await
+            .context("describing rules for listener")?;
+        rules.extend_from_slice(page.rules());
+
+        match page.next_marker() {
+            Some(next) => marker = Some(next.to_string()),
+            None => break,
+        }
+    }
+    Ok(rules)
+}
+
+/// Fetch the tags of every rule that is not a default rule, by rule ARN
+///
+/// Rules without tags are left out. Credentials that cannot describe tags only lose the tags.
+async fn describe_rule_tags(
+    client: &aws_sdk_elasticloadbalancingv2::Client,
+    rules_by_listener: &[Option<Vec<Rule>>],
+) -> eyre::Result<HashMap<String, RuleTags>> {
+    // default rules cannot be tagged
+    let arns: Vec<String> = rules_by_listener
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|rule| rule.is_default() != Some(true))
+        .filter_map(|rule| rule.rule_arn().map(|arn| arn.to_string()))
+        .collect();
+
+    let mut tags_by_rule = HashMap::new();
+    for batch in arns.chunks(MAX_TAGGED_RESOURCES_PER_CALL) {
+        let response = match client
+            .describe_tags()
+            .set_resource_arns(Some(batch.to_vec()))
+            .send()
+            .instrument(info_span!("describe_tags", rules = batch.len()))
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if is_access_denied(e.code()) => {
+                tracing::warn!("not allowed to describe listener rule tags, not showing them");
+                return Ok(HashMap::new());
+            }
+            Err(e) => return Err(e).context("describing listener rule tags"),
+        };
+        for description in response.tag_descriptions() {
+            let mut tags: Vec<(String, String)> = description
+                .tags()
+                .iter()
+                .filter_map(|tag| {
+                    Some((
+                        tag.key()?.to_string(),
+                        tag.value().unwrap_or_default().to_string(),
+                    ))
+                })
+                .collect();
+            if tags.is_empty() {
+                continue;
+            }
+            tags.sort();
+            if let Some(arn) = description.resource_arn() {
+                tags_by_rule.insert(arn.to_string(), RuleTags(tags));
+            }
+        }
+    }
+    Ok(tags_by_rule)
+}
+
+/// Fetch the attributes and targets of each target group, queued after their target group, along
+/// with the typed model of each target group
+///
+/// Each target group shows how many of its targets are healthy, and is marked if none are.
+async fn describe_target_group_details(
+    client: aws_sdk_elasticloadbalancingv2::Client,
+    target_groups: Vec<TargetGroup>,
+    options: AlbOptions,
+) -> eyre::Result<(Vec<Queued>, Vec<TargetGroupNode>)> {
+    let AlbOptions {
+        attributes,
+        sort,
+        show_empty,
+        health_retries,
+        only_unhealthy,
+        ..
+    } = options;
+    let mut out: Vec<Queued> = Vec::new();
+    let mut nodes = Vec::new();
+    for target_group in target_groups {
+        let tg_arn = if let Some(arn) = target_group.target_group_arn() {
+            arn.to_string()
+        } else {
+            nodes.push(TargetGroupNode::new(&target_group, &[]));
+            out.push((1, Box::new(target_group)));
+            continue;
+        };
+
+        // - attributes
+        let tg_attributes = if attributes {
+            let tg_attributes = client
+                .describe_target_group_attributes()
+                .target_group_arn(&tg_arn)
+                .send()
+                .instrument(info_span!(
+                    "describe_target_group_attributes",
+                    target_group_arn = tg_arn
+                ))
+                .await
+                .wrap_err("describing target group attributes")?;
+            Some(TargetGroupAttributes::from_attributes(
+                tg_attributes.attributes(),
+            ))
+        } else {
+            None
+        };
+        // part of the target group itself, so shown with the attributes without another call
+        let health_check = attributes.then(|| HealthCheckSettings(target_group.clone()));
+
+        // - targets
+        let targets = match describe_target_health(&client, &tg_arn, health_retries).await {
+            Ok(targets) => {
+                targets.ok_or_else(|| Box::new(HealthUnavailable("throttled")) as Box<dyn Present>)
+            }
+            Err(e) if is_access_denied_report(&e) => {
+                Err(Box::new(AccessDenied) as Box<dyn Present>)
+            }
+            Err(e) => return Err(e),
+        };
+        let mut targets = match targets {
+            Ok(targets) => targets,
+            Err(unavailable) => {
+                // the rest of the tree is still useful without this target group's health
+                nodes.push(TargetGroupNode::new(&target_group, &[]));
+                out.push((1, Box::new(target_group)));
+                if let Some(tg_attributes) = tg_attributes {
+                    out.push((2, Box::new(tg_attributes)));
+                }
+                if let Some(health_check) = health_check {
+                    out.push((2, Box::new(health_check)));
+                }
+                out.push((2, unavailable));
+                continue;
+            }
+        };
+        sort_targets(&mut targets, sort);
+
+        nodes.push(TargetGroupNode::new(&target_group, &targets));
+        if only_unhealthy && targets.iter().all(is_healthy) {
+            continue;
+        }
+        out.push((1, Box::new(TargetGroupHealth::new(target_group, &targets))));
+        if let Some(tg_attributes) = tg_attributes {
+            out.push((2, Box::new(tg_attributes)));
+        }
+        if let Some(health_check) = health_check {
+            out.push((2, Box::new(health_check)));
+        }
+        if show_empty && targets.is_empty() {
+            out.push((2, Box::new(Empty("targets"))));
+        }
+        if let Some(zones) = ZoneDistribution::from_targets(&targets) {
+            out.push((2, Box::new(zones)));
+        }
+        for target in targets {
+            if only_unhealthy && is_healthy(&target) {
+                continue;
+            }
+            let failure = target
+                .target_health()
+                .and_then(HealthCheckFailure::from_health);
+            out.push((2, Box::new(target)));
+            if let Some(failure) = failure {
+                out.push((3, Box::new(failure)));
+            }
+        }
+    }
+    Ok((out, nodes))
+}
+
+/// Fetch the health of a target group's targets, retrying with exponential backoff while throttled
+///
+/// Returns `None` if the call is still throttled after `retries` more attempts; other errors are
+/// returned straight away.
+async fn describe_target_health(
+    client: &aws_sdk_elasticloadbalancingv2::Client,
+    tg_arn: &str,
+    retries: u32,
+) -> eyre::Result<Option<Vec<TargetHealthDescription>>> {
+    let mut delay = INITIAL_HEALTH_RETRY_DELAY;
+    for attempt in 0..=retries {
+        let result = client
+            .describe_target_health()
+            .target_group_arn(tg_arn)
+            .send()
+            .instrument(info_span!(
+                "describe_target_health",
+                target_group_arn = tg_arn,
+                attempt
+            ))
+            .await;
+        match result {
+            Ok(response) => return Ok(Some(response.target_health_descriptions().to_vec())),
+            Err(e) if crate::error::is_throttling(e.code()) => {
+                tracing::warn!(
+                    target_group_arn = tg_arn,
+                    attempt,
+                    "describe_target_health throttled"
+                );
+                if attempt < retries {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+            Err(e) => {
+                return Err(eyre::Report::new(e).wrap_err("describing targets in target group"));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn is_healthy(target: &TargetHealthDescription) -> bool {
+    target.target_health().and_then(|h| h.state()) == Some(&TargetHealthStateEnum::Healthy)
+}
+
+/// Queue each listener with its rules, their tags and their actions
+///
+/// With `routes_to`, only rules with an action forwarding to one of those target groups are
+/// queued, along with their listeners.
+fn queue_listeners(
+    listeners: &[Listener],
+    rules_by_listener: &[Option<Vec<Rule>>],
+    rules_denied: &[bool],
+    rule_tags: &HashMap<String, RuleTags>,
+    target_group_names: &Arc<HashMap<String, String>>,
+    options: &AlbOptions,
+    routes_to: Option<&HashSet<String>>,
+) -> Vec<Queued> {
+    let mut out: Vec<Queued> = Vec::new();
+    for ((listener, rules), &denied) in listeners.iter().zip(rules_by_listener).zip(rules_denied) {
+        let Some(rules) = rules else {
+            if routes_to.is_none() {
+                out.push((1, Box::new(listener.clone())));
+                if denied {
+                    out.push((2, Box::new(AccessDenied)));
+                }
+            }
+            continue;
+        };
+        let shown: Vec<&Rule> = rules
+            .iter()
+            .filter(|rule| {
+                routes_to.is_none_or(|routes_to| {
+                    rule.actions().iter().any(|action| {
+                        forward_target_group_arns(action)
+                            .iter()
+                            .any(|arn| routes_to.contains(*arn))
+                    })
+                })
+            })
+            .collect();
+        if routes_to.is_some() && shown.is_empty() {
+            continue;
+        }
+
+        out.push((1, Box::new(listener.clone())));
+        if options.show_empty && rules.is_empty() {
+            out.push((2, Box::new(Empty("rules"))));
+        }
+
+        let matched = options
+            .test_request
+            .as_ref()
+            .and_then(|req| match_request(rules, &req.host, &req.path, &req.method));
+        if options.test_request.is_some() && matched.is_none() {
+            tracing::warn!(
+                "no rule on listener port={} matches the test request",
+                listener.port().unwrap_or_default()
+            );
+        }
+
+        for rule in shown {
+            if matched.is_some_and(|(m, _)| std::ptr::eq(m, rule)) {
+                out.push((2, Box::new(Matched(rule.clone()))));
+            } else {
+                out.push((2, Box::new(rule.clone())));
+            }
+            if let Some(tags) = rule.rule_arn().and_then(|arn| rule_tags.get(arn)) {
+                out.push((3, Box::new(tags.clone())));
+            }
+
+            let actions = ordered_actions(rule);
+            let show_order = actions.len() > 1;
+            for action in actions {
+                let action_info = ActionInfo {
+                    action: action.clone(),
+                    target_group_names: Arc::clone(target_group_names),
+                    show_order,
+                };
+                if matched.is_some_and(|(_, a)| std::ptr::eq(a, action)) {
+                    out.push((3, Box::new(Matched(action_info))));
+                } else {
+                    out.push((3, Box::new(action_info)));
+                }
+            }
+        }
+
+        // the triage view already only shows the routes to unhealthy target groups
+        let routes = ListenerRoutes::from_rules(rules, target_group_names);
+        if let Some(routes) = routes.filter(|_| routes_to.is_none()) {
+            out.push((2, Box::new(routes)));
+        }
+    }
+    out
+}
+
+/// Resolve a load balancer name, partial ARN or id to its full ARN
+///
+/// Full ARNs are returned as they are without calling AWS.
+pub async fn resolve_lb_arn(
+    client: &aws_sdk_elasticloadbalancingv2::Client,
+    input: &str,
+) -> Result<String, LbtreeError> {
+    if input.starts_with("arn:") {
+        return Ok(input.to_string());
+    }
+
+    let load_balancers = collect_all(
+        client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing load balancers",
+    )
+    .instrument(info_span!("describe_load_balancers"))
+    .await?;
+    let matches = load_balancers
+        .iter()
+        .filter_map(|lb| lb.load_balancer_arn().map(|arn| (lb, arn)))
+        .filter(|(lb, arn)| lb.load_balancer_name() == Some(input) || arn_has_suffix(arn, input))
+        .map(|(_, arn)| arn.to_string())
+        .collect();
+    Ok(unique_match("load balancer", input, matches)?)
+}
+
+/// Fetch an Application Load Balancer hierarchy without writing any output
+///
+/// Returns `None` if no ARN was given and the user did not select a load balancer.
+pub async fn fetch_alb_tree(
+    config: &SdkConfig,
+    arn: Option<String>,
+    options: AlbOptions,
+) -> Result<Option<Node>, LbtreeError> {
+    Ok(fetch_alb(config, arn, options).await?.map(|(tree, _)| tree))
+}
+
+/// Fetch the typed model of an Application Load Balancer, e.g. for `--output model`
+///
+/// Returns `None` if no ARN was given and the user did not select a load balancer.
+pub async fn fetch_alb_model(
+    config: &SdkConfig,
+    arn: Option<String>,
+    options: AlbOptions,
+) -> Result<Option<AlbTree>, LbtreeError> {
+    Ok(fetch_alb(config, arn, options)
+        .await?
+        .map(|(_, model)| model))
+}
+
+/// Fetch both the rendered tree and the typed model of a load balancer, built from the same
+/// responses so the two never disagree
+///
+/// The listeners (with their rules) and the target groups (with their targets) are fetched by
+/// separate tasks that overlap in time, but nothing is written until both have finished. Each
+/// task collects its subtree into its own `Vec<Queued>`, and the subtrees are joined in a fixed
+/// order regardless of which task finishes first: the load balancer's own details, then the
+/// listeners, then the target groups. A new subtree fetched in parallel should follow the same
+/// contract, returning its queued lines from its task and being appended at a fixed position,
+/// so the output stays deterministic for snapshots.
+#[instrument(skip(config))]
+async fn fetch_alb(
+    config: &SdkConfig,
+    arn: Option<String>,
+    options: AlbOptions,
+) -> eyre::Result<Option<(Node, AlbTree)>> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+
+    let lb_arn = if let Some(arn) = arn {
+        resolve_lb_arn(&client, &arn).await?
+    } else {
+        match select_load_balancer(&client, options.max_items).await? {
+            Some(arn) => arn,
+            None => return Ok(None),
+        }
+    };
+
+    let spinner = Spinner::new("Fetching load balancer details");
+    let AlbOptions {
+        attributes,
+        waf,
+        sort,
+        only_unhealthy,
+        ..
+    } = options;
+
+    let load_balancer = client
+        .describe_load_balancers()
+        .load_balancer_arns(&lb_arn)
+        .send()
+        .instrument(info_span!("describe_load_balancers"))
+        .await
+        .map_err(|e| match e.as_service_error() {
+            Some(service_error) if service_error.is_load_balancer_not_found_exception() => {
+                eyre::Report::new(NotFound::new("load balancer", &lb_arn))
+            }
+            _ => eyre::Report::new(e).wrap_err("describing load balancer"),
+        })?;
+    let lb = load_balancer
+        .load_balancers()
+        .first()
+        .ok_or_else(|| NotFound::new("load balancer", &lb_arn))?;
+
+    // target groups are needed up front to show names in forward actions
+    let mut target_groups = collect_all(
+        client
+            .describe_target_groups()
+            .load_balancer_arn(&lb_arn)
+            .into_paginator()
+            .items()
+            .send(),
+        "describing target groups",
+    )
+    .instrument(info_span!("describe_target_groups"))
+    .await?;
+    sort_target_groups(&mut target_groups, sort);
+    let target_group_names: Arc<HashMap<String, String>> = Arc::new(
+        target_groups
+            .iter()
+            .filter_map(|tg| {
+                Some((
+                    tg.target_group_arn()?.to_string(),
+                    tg.target_group_name()?.to_string(),
+                ))
+            })
+            .collect(),
+    );
+
+    // parallel fetch of the results
+
+    let listeners_client = client.clone();
+    let listeners_lb_arn = lb_arn.clone();
+    let listeners_fut: JoinHandle<eyre::Result<ListenersWithRules>> = tokio::spawn(
+        async move {
+            let mut listeners = collect_all(
+                listeners_client
+                    .describe_listeners()
+                    .load_balancer_arn(listeners_lb_arn)
+                    .into_paginator()
+                    .items()
+                    .send(),
+                "describing listeners for load balancer",
+            )
+            .instrument(info_span!("describe_listeners"))
+            .await?;
+            sort_listeners(&mut listeners);
+
+            // - rules, fetched for several listeners at once and presented in listener order
+            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_RULE_FETCHES));
+            let mut rule_fetches = JoinSet::new();
+            for (i, listener) in listeners.iter().enumerate() {
+                let Some(listener_arn) = listener.listener_arn().map(|arn| arn.to_string()) else {
+                    continue;
+                };
+                let client = listeners_client.clone();
+                let semaphore = Arc::clone(&semaphore);
+                rule_fetches.spawn(
+                    async move {
+                        // panic safety: the semaphore is never closed
+                        let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                        let rules = describe_all_rules(&client, &listener_arn)
+                            .instrument(info_span!(
+                                "describe_rules",
+                                listener_arn = listener_arn.as_str()
+                            ))
+                            .await;
+                        // the listener is still shown under a role that cannot see its rules
+                        let mut rules = match rules {
+                            Ok(rules) => rules,
+                            Err(e) if is_access_denied_report(&e) => {
+                                return Ok((i, None));
+                            }
+                            Err(e) => return Err(e),
+                        };
+                        rules.sort_by_key(evaluation_order);
+                        Ok::<_, eyre::Report>((i, Some(rules)))
+                    }
+                    .in_current_span(),
+                );
+            }
+            let mut rules_by_listener: Vec<Option<Vec<Rule>>> = vec![None; listeners.len()];
+            let mut rules_denied = vec![false; listeners.len()];
+            while let Some(result) = rule_fetches.join_next().await {
+                match result.context("rule fetch task panicked")?? {
+                    (i, Some(rules)) => rules_by_listener[i] = Some(rules),
+                    (i, None) => rules_denied[i] = true,
+                }
+            }
+
+            Ok((listeners, rules_by_listener, rules_denied))
+        }
+        .instrument(info_span!("listeners")),
+    );
+    let known_target_groups: HashSet<String> = target_groups
+        .iter()
+        .filter_map(|tg| tg.target_group_arn().map(|arn| arn.to_string()))
+        .collect();
+    #[allow(clippy::type_complexity)]
+    let target_groups_fut: JoinHandle<eyre::Result<(Vec<Queued>, Vec<TargetGroupNode>)>> =
+        tokio::spawn(
+            describe_target_group_details(client.clone(), target_groups, options.clone())
+                .instrument(info_span!("target_groups")),
+        );
+
+    // the triage view only shows what routes to unhealthy targets
+    let mut children: Vec<Queued> = Vec::new();
+    if !only_unhealthy {
+        for address in StaticAddress::from_load_balancer(lb) {
+            children.push((1, Box::new(address)));
+        }
+    }
+    if attributes && !only_unhealthy {
+        let lb_attributes = client
+            .describe_load_balancer_attributes()
+            .load_balancer_arn(&lb_arn)
+            .send()
+            .instrument(info_span!("describe_load_balancer_attributes"))
+            .await
+            .context("describing load balancer attributes")?;
+        let mut lb_attributes = LoadBalancerAttributes::from_attributes(lb_attributes.attributes());
+        lb_attributes.ip_address_type = lb.ip_address_type().map(|t| t.as_str().to_string());
+        children.push((1, Box::new(lb_attributes.access_logs.clone())));
+        children.push((1, Box::new(lb_attributes)));
+    }
+    if waf && !only_unhealthy {
+        let waf_client = aws_sdk_wafv2::Client::new(config);
+        let web_acl = waf_client
+            .get_web_acl_for_resource()
+            .resource_arn(&lb_arn)
+            .send()
+            .instrument(info_span!("get_web_acl_for_resource"))
+            .await
+            .context("fetching web ACL for load balancer")?;
+        children.push((
+            1,
+            Box::new(WafAssociation {
+                web_acl_name: web_acl
+                    .web_acl()
+                    .and_then(|acl| acl.name())
+                    .map(|name| name.to_string()),
+            }),
+        ));
+    }
+    let (listeners, rules_by_listener, rules_denied) = listeners_fut.await??;
+    let rule_tags = if options.tags {
+        describe_rule_tags(&client, &rules_by_listener).await?
+    } else {
+        HashMap::new()
+    };
+    let listener_nodes: Vec<ListenerNode> = listeners
+        .iter()
+        .zip(&rules_by_listener)
+        .map(|(listener, rules)| ListenerNode::new(listener, rules.as_deref().unwrap_or_default()))
+        .collect();
+    // target groups that forward actions send requests to
+    let referenced: BTreeSet<String> = rules_by_listener
+        .iter()
+        .flatten()
+        .flatten()
+        .flat_map(|rule| rule.actions())
+        .flat_map(forward_target_group_arns)
+        .map(|arn| arn.to_string())
+        .collect();
+    let (mut target_group_children, mut target_group_nodes) = target_groups_fut.await??;
+
+    // a target group can be routed to without being returned when filtering by load balancer,
+    // e.g. when it is only referenced from a weighted forward config
+    let missing: Vec<String> = referenced
+        .into_iter()
+        .filter(|arn| !known_target_groups.contains(arn))
+        .collect();
+    if !missing.is_empty() {
+        let mut missing_target_groups = collect_all(
+            client
+                .describe_target_groups()
+                .set_target_group_arns(Some(missing))
+                .into_paginator()
+                .items()
+                .send(),
+            "describing target groups referenced by forward actions",
+        )
+        .instrument(info_span!("describe_target_groups", referenced = true))
+        .await?;
+        sort_target_groups(&mut missing_target_groups, sort);
+        let (missing_children, missing_nodes) =
+            describe_target_group_details(client.clone(), missing_target_groups, options.clone())
+                .await?;
+        target_group_children.extend(missing_children);
+        target_group_nodes.extend(missing_nodes);
+    }
+    drop(spinner);
+
+    let unhealthy: HashSet<String> = target_group_nodes
+        .iter()
+        .filter(|tg| {
+            tg.targets
+                .iter()
+                .any(|target| target.state.as_deref() != Some("healthy"))
+        })
+        .filter_map(|tg| tg.arn.clone())
+        .collect();
+    children.extend(queue_listeners(
+        &listeners,
+        &rules_by_listener,
+        &rules_denied,
+        &rule_tags,
+        &target_group_names,
+        &options,
+        only_unhealthy.then_some(&unhealthy),
+    ));
+    children.extend(target_group_children);
+
+    let model = AlbTree {
+        schema_version: model::SCHEMA_VERSION,
+        load_balancer: LoadBalancerNode::from(lb),
+        listeners: listener_nodes,
+        target_groups: target_group_nodes,
+    };
+    let tree = if options.age {
+        let aged = Aged {
+            inner: lb.clone(),
+            created: lb.created_time().map(|created| created.secs()),
+            now: SystemTime::now(),
+        };
+        build_tree(&aged, &children)
+    } else {
+        build_tree(lb, &children)
+    };
+    Ok(Some((tree, model)))
+}
+
+/// Display an Application Load Balancer hierarchy
+///
+/// Returns [`LbtreeError::NoSelection`] if the user does not choose a resource to display.
+pub async fn display_alb(
+    config: &SdkConfig,
+    arn: Option<String>,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let only_unhealthy = options.only_unhealthy;
+    let tree = fetch_alb_tree(config, arn, options)
+        .await?
+        .ok_or(NothingSelected("load balancer"))?;
+    if only_unhealthy && tree.children.is_empty() {
+        writer.write_line("All targets healthy");
+        return Ok(());
+    }
+    tree.render(writer);
+
+    Ok(())
+}
+
+/// Display a single target group with the health of its targets, without the load balancer it
+/// belongs to
+///
+/// Of the options, only those about target groups and targets apply.
+pub async fn display_target_group(
+    config: &SdkConfig,
+    arn: &str,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+
+    let spinner = Spinner::new("Fetching target group");
+    let response = client
+        .describe_target_groups()
+        .target_group_arns(arn)
+        .send()
+        .instrument(info_span!("describe_target_groups"))
+        .await
+        .map_err(|e| match e.as_service_error() {
+            Some(service_error) if service_error.is_target_group_not_found_exception() => {
+                eyre::Report::new(NotFound::new("target group", arn))
+            }
+            _ => eyre::Report::new(e).wrap_err("describing target group"),
+        })?;
+    let target_group = response
+        .target_groups()
+        .first()
+        .cloned()
+        .ok_or_else(|| NotFound::new("target group", arn))?;
+
+    // the load balancers routing to the target group, looked up for their names
+    let lb_arns = target_group.load_balancer_arns().to_vec();
+    let load_balancers = if lb_arns.is_empty() {
+        Vec::new()
+    } else {
+        client
+            .describe_load_balancers()
+            .set_load_balancer_arns(Some(lb_arns))
+            .send()
+            .instrument(info_span!("describe_load_balancers"))
+            .await
+            .context("describing load balancers using the target group")?
+            .load_balancers()
+            .to_vec()
+    };
+    let show_empty = options.show_empty;
+    let (queued, _) = describe_target_group_details(client, vec![target_group], options).await?;
+    drop(spinner);
+
+    // the target group is queued first, one level below where a load balancer would be
+    let mut queued = queued.into_iter();
+    let Some((_, root)) = queued.next() else {
+        // with --only-unhealthy, a target group with only healthy targets is left out
+        writer.write_line("All targets healthy");
+        return Ok(());
+    };
+    let mut descendants: Vec<Queued> = Vec::new();
+    if show_empty && load_balancers.is_empty() {
+        descendants.push((1, Box::new(Empty("load balancers"))));
+    }
+    for lb in load_balancers {
+        descendants.push((1, Box::new(AttachedLoadBalancer(lb))));
+    }
+    descendants.extend(queued.map(|(depth, item)| (depth - 1, item)));
+    build_tree(root.as_ref(), &descendants).render(writer);
+
+    Ok(())
+}
+
+/// Fetch the hierarchy of a load balancer that was just listed, returning `None` with a warning if
+/// it has since been deleted
+async fn fetch_listed_alb(
+    config: &SdkConfig,
+    arn: String,
+    options: AlbOptions,
+) -> eyre::Result<Option<(Node, AlbTree)>> {
+    match fetch_alb(config, Some(arn), options).await {
+        // an ARN is given, so there is no selection to abandon
+        Ok(tree) => Ok(tree),
+        Err(e) => match e.downcast_ref::<NotFound>() {
+            Some(not_found) => {
+                tracing::warn!("{not_found}, skipping");
+                Ok(None)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Display the hierarchy of every load balancer whose name matches `pattern`
+///
+/// The pattern uses the same `*` and `?` wildcards as listener rule conditions. Each load balancer
+/// is rendered as its own tree, in the order they are returned by AWS.
+pub async fn display_albs_by_name(
+    config: &SdkConfig,
+    pattern: &str,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+
+    let spinner = Spinner::new("Fetching load balancers");
+    let load_balancers = collect_all(
+        client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing load balancers",
+    )
+    .instrument(info_span!("describe_load_balancers"))
+    .await?;
+    drop(spinner);
+
+    let arns: Vec<String> = load_balancers
+        .iter()
+        .filter(|lb| {
+            lb.load_balancer_name()
+                .is_some_and(|name| wildcard_match(pattern, name))
+        })
+        .filter_map(|lb| lb.load_balancer_arn().map(|arn| arn.to_string()))
+        .collect();
+    if arns.is_empty() {
+        return Err(eyre::eyre!("No load balancer name matches {pattern:?}").into());
+    }
+    // the load balancers left out are not fetched at all
+    let left_out = options
+        .max_items
+        .map(|max| arns.len().saturating_sub(max))
+        .unwrap_or_default();
+
+    // a load balancer deleted since listing is skipped, so separate only the trees rendered
+    let mut rendered = false;
+    for (i, arn) in arns.into_iter().enumerate() {
+        if options.max_items.is_some_and(|max| i >= max) {
+            break;
+        }
+        let Some((tree, _)) = fetch_listed_alb(config, arn, options.clone()).await? else {
+            continue;
+        };
+        if rendered {
+            writer.write_separator();
+        }
+        tree.render(writer);
+        rendered = true;
+    }
+    if left_out > 0 {
+        if rendered {
+            writer.write_separator();
+        }
+        writer.write_line(&format!("... (truncated, {left_out} more)"));
+    }
+
+    Ok(())
+}
+
+/// Display a one line summary of every load balancer, or of those whose name matches `pattern`,
+/// instead of their trees
+///
+/// See [`AlbTree::summary`] for the columns of each line.
+pub async fn display_alb_summaries(
+    config: &SdkConfig,
+    pattern: Option<&str>,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+
+    let spinner = Spinner::new("Fetching load balancers");
+    let load_balancers = collect_all(
+        client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing load balancers",
+    )
+    .instrument(info_span!("describe_load_balancers"))
+    .await?;
+    drop(spinner);
+
+    let arns: Vec<String> = load_balancers
+        .iter()
+        .filter(|lb| {
+            pattern.is_none_or(|pattern| {
+                lb.load_balancer_name()
+                    .is_some_and(|name| wildcard_match(pattern, name))
+            })
+        })
+        .filter_map(|lb| lb.load_balancer_arn().map(|arn| arn.to_string()))
+        .collect();
+    if let Some(pattern) = pattern.filter(|_| arns.is_empty()) {
+        return Err(eyre::eyre!("No load balancer name matches {pattern:?}").into());
+    }
+
+    for arn in arns {
+        if let Some((_, model)) = fetch_listed_alb(config, arn, options.clone()).await? {
+            writer.write_line(&model.summary());
+        }
+    }
+
+    Ok(())
+}
+
+/// Count the load balancer given by name, ARN or partial ARN, or every load balancer, with their
+/// listeners and target groups
+///
+/// Rules and targets are not fetched, so each listener and target group is assumed to need one
+/// more call for a full render.
+pub async fn estimate_alb(
+    config: &SdkConfig,
+    arn: Option<String>,
+    attributes: bool,
+    waf: bool,
+    tags: bool,
+) -> Result<Estimate, LbtreeError> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+    let mut estimate = Estimate::default();
+
+    let lb_arns = match arn {
+        Some(arn) => vec![resolve_lb_arn(&client, &arn).await?],
+        None => collect_all(
+            client
+                .describe_load_balancers()
+                .into_paginator()
+                .items()
+                .send(),
+            "describing load balancers",
+        )
+        .instrument(info_span!("describe_load_balancers"))
+        .await?
+        .iter()
+        .filter_map(|lb| lb.load_balancer_arn().map(|arn| arn.to_string()))
+        .collect(),
+    };
+    estimate.count("load balancers", lb_arns.len());
+
+    for lb_arn in &lb_arns {
+        let listeners = collect_all(
+            client
+                .describe_listeners()
+                .load_balancer_arn(lb_arn)
+                .into_paginator()
+                .items()
+                .send(),
+            "describing listeners for load balancer",
+        )
+        .instrument(info_span!("describe_listeners"))
+        .await?;
+        let target_groups = collect_all(
+            client
+                .describe_target_groups()
+                .load_balancer_arn(lb_arn)
+                .into_paginator()
+                .items()
+                .send(),
+            "describing target groups",
+        )
+        .instrument(info_span!("describe_target_groups"))
+        .await?;
+        estimate.count("listeners", listeners.len());
+        estimate.count("target groups", target_groups.len());
+
+        // the load balancer, its listeners and target groups, then one call per listener for
+        // its rules and one per target group for its targets
+        estimate.calls += 3 + listeners.len() + target_groups.len();
+        if attributes {
+            estimate.calls += 1 + target_groups.len();
+        }
+        if waf {
+            estimate.calls += 1;
+        }
+        // rules are not counted, so assume a listener's rule tags fit in one call
+        if tags {
+            estimate.calls += listeners.len();
+        }
+    }
+
+    Ok(estimate)
+}
+
+/// Display every load balancer in the region, grouped by type, without selecting one
+///
+/// Each group is a tree whose root names the type, with one subtree per load balancer.
+pub async fn display_all_albs(
+    config: &SdkConfig,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let groups = fetch_all_albs(config, options).await?;
+
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            writer.write_separator();
+        }
+        group.render(writer);
+    }
+
+    Ok(())
+}
+
+/// Display every load balancer in every region enabled in the account, e.g. for `--region all`
+///
+/// Regions are fetched concurrently and each is rendered as a tree whose root names the region,
+/// with the same groups as [`display_all_albs`] beneath it. Listing the regions needs the
+/// `ec2:DescribeRegions` permission on top of those for a single region.
+pub async fn display_all_albs_in_all_regions(
+    config: &SdkConfig,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let ec2_client = aws_sdk_ec2::Client::new(config);
+    let regions = ec2_client
+        .describe_regions()
+        .send()
+        .instrument(info_span!("describe_regions"))
+        .await
+        .context("describing enabled regions")?;
+    let regions: Vec<String> = regions
+        .regions()
+        .iter()
+        .filter_map(|region| region.region_name().map(|name| name.to_string()))
+        .collect();
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REGION_FETCHES));
+    let mut region_fetches = JoinSet::new();
+    for region in regions {
+        let config = config
+            .to_builder()
+            .region(Region::new(region.clone()))
+            .build();
+        let options = options.clone();
+        let semaphore = Arc::clone(&semaphore);
+        region_fetches.spawn(
+            async move {
+                // panic safety: the semaphore is never closed
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let groups = fetch_all_albs(&config, options)
+                    .await
+                    .wrap_err_with(|| format!("fetching load balancers in {region}"))?;
+                Ok::<_, eyre::Report>((region, groups))
+            }
+            .instrument(info_span!("region")),
+        );
+    }
+    let mut by_region: BTreeMap<String, Vec<Node>> = BTreeMap::new();
+    while let Some(result) = region_fetches.join_next().await {
+        let (region, groups) = result.context("region fetch task panicked")??;
+        by_region.insert(region, groups);
+    }
+
+    for (i, (region, groups)) in by_region.into_iter().enumerate() {
+        if i > 0 {
+            writer.write_separator();
+        }
+        let mut root = Node::new(format!("Region {region}"));
+        root.children = groups;
+        root.render(writer);
+    }
+
+    Ok(())
+}
+
+/// Display every load balancer reachable with each of the given profiles, one tree per profile
+///
+/// A profile whose load balancers cannot be fetched, e.g. because its SSO session has expired, is
+/// reported on stderr and left out rather than failing the whole sweep.
+pub async fn display_all_albs_in_profiles(
+    profiles: Vec<(String, SdkConfig)>,
+    options: AlbOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PROFILE_FETCHES));
+    let mut profile_fetches = JoinSet::new();
+    for (profile, config) in profiles {
+        let options = options.clone();
+        let semaphore = Arc::clone(&semaphore);
+        profile_fetches.spawn(
+            async move {
+                // panic safety: the semaphore is never closed
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let groups = fetch_all_albs(&config, options).await;
+                (profile, groups)
+            }
+            .instrument(info_span!("profile")),
+        );
+    }
+    let mut by_profile: BTreeMap<String, Vec<Node>> = BTreeMap::new();
+    while let Some(result) = profile_fetches.join_next().await {
+        let (profile, groups) = result.context("profile fetch task panicked")?;
+        match groups {
+            Ok(groups) => {
+                by_profile.insert(profile, groups);
+            }
+            Err(e) => tracing::warn!("profile {profile}: {e:#}, skipping"),
+        }
+    }
+
+    for (i, (profile, groups)) in by_profile.into_iter().enumerate() {
+        if i > 0 {
+            writer.write_separator();
+        }
+        let mut root = Node::new(format!("Profile {profile}"));
+        root.children = groups;
+        root.render(writer);
+    }
+
+    Ok(())
+}
+
+/// Fetch every load balancer in the region, as one tree per load balancer type
+async fn fetch_all_albs(config: &SdkConfig, options: AlbOptions) -> eyre::Result<Vec<Node>> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+
+    let spinner = Spinner::new("Fetching load balancers");
+    let load_balancers = collect_all(
+        client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing load balancers",
+    )
+    .instrument(info_span!("describe_load_balancers"))
+    .await?;
+    drop(spinner);
+
+    let mut by_type: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for lb in &load_balancers {
+        if let Some(arn) = lb.load_balancer_arn() {
+            let lb_type = lb.r#type().map(|t| t.as_str()).unwrap_or("unknown");
+            by_type.entry(lb_type).or_default().push(arn.to_string());
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (lb_type, arns) in by_type {
+        let mut group = Node::new(format!(
+            "Load balancers type={lb_type} count={}",
+            arns.len()
+        ));
+        for arn in arns {
+            if let Some((tree, _)) = fetch_listed_alb(config, arn, options.clone()).await? {
+                group.children.push(tree);
+            }
+        }
+        groups.push(group);
+    }
+
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_elasticloadbalancingv2::types::{
+        AuthenticateCognitoActionConfig, AuthenticateOidcActionConfig, AvailabilityZone,
+        FixedResponseActionConfig, ForwardActionConfig, LoadBalancerAddress,
+        LoadBalancerSchemeEnum, LoadBalancerState, LoadBalancerStateEnum, Matcher,
+        MutualAuthenticationAttributes, ProtocolEnum, RedirectActionConfig,
+        RedirectActionStatusCodeEnum, TargetDescription, TargetGroupTuple, TargetHealthReasonEnum,
+        TargetTypeEnum,
+    };
+
+    #[test]
+    fn test_load_balancer_content() {
+        let lb = LoadBalancer::builder()
+            .dns_name("lb.example.com")
+            .scheme(LoadBalancerSchemeEnum::Internal)
+            .security_groups("sg-1")
+            .security_groups("sg-2")
+            .vpc_id("vpc-1")
+            .availability_zones(
+                AvailabilityZone::builder()
+                    .zone_name("us-east-1a")
+                    .subnet_id("subnet-1")
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            lb.content(),
+            "Load balancer (lb.example.com) scheme=internal sgs=[sg-1, sg-2] vpc=vpc-1 azs=[us-east-1a/subnet-1]"
+        );
+
+        assert_eq!(
+            LoadBalancer::builder().build().content(),
+            "Load balancer (unknown) scheme=unknown sgs=[] vpc=unknown azs=[]"
+        );
+
+        let dualstack = LoadBalancer::builder()
+            .dns_name("dualstack.lb.example.com")
+            .scheme(LoadBalancerSchemeEnum::InternetFacing)
+            .ip_address_type(IpAddressType::Dualstack)
+            .build();
+        assert_eq!(
+            dualstack.content(),
+            "Load balancer (dualstack.lb.example.com) scheme=internet-facing ip-address-type=dualstack sgs=[] vpc=unknown azs=[]"
+        );
+        let ipv4 = LoadBalancer::builder()
+            .ip_address_type(IpAddressType::Ipv4)
+            .build();
+        assert!(!ipv4.content().contains("ip-address-type"));
+    }
+
+    #[test]
+    fn test_ipv6_target_content() {
+        let target = TargetHealthDescription::builder()
+            .target(
+                TargetDescription::builder()
+                    .id("2001:db8:1234::5")
+                    .port(8080)
+                    .build(),
+            )
+            .target_health(
+                TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Healthy)
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            target.content(),
+            "Target id=2001:db8:1234::5 port=8080 state=healthy"
+        );
+        assert_eq!(target.arn().as_deref(), Some("2001:db8:1234::5"));
+    }
+
+    #[test]
+    fn test_load_balancer_content_truncates_zones() {
+        let mut builder = LoadBalancer::builder();
+        for zone in ["a", "b", "c", "d", "e"] {
+            builder = builder.availability_zones(
+                AvailabilityZone::builder()
+                    .zone_name(format!("us-east-1{zone}"))
+                    .subnet_id(format!("subnet-{zone}"))
+                    .build(),
+            );
+        }
+        assert!(builder.build().content().ends_with(
+            "azs=[us-east-1a/subnet-a, us-east-1b/subnet-b, us-east-1c/subnet-c, +2 more]"
+        ));
+    }
+
+    #[test]
+    fn test_listener_content() {
+        let listener = Listener::builder()
+            .protocol(ProtocolEnum::Https)
+            .port(443)
+            .build();
+        assert_eq!(listener.content(), "Listener protocol=HTTPS port=443");
+        assert_eq!(
+            Listener::builder().build().content(),
+            "Listener protocol=unknown port=unknown"
+        );
+    }
+
+    #[test]
+    fn test_listener_content_with_alpn_and_mutual_tls() {
+        let tls = Listener::builder()
+            .protocol(ProtocolEnum::Tls)
+            .port(443)
+            .alpn_policy("HTTP2Preferred")
+            .build();
+        assert_eq!(
+            tls.content(),
+            "Listener protocol=TLS port=443 alpn=HTTP2Preferred"
+        );
+
+        let mtls = |mode: &str| {
+            Listener::builder()
+                .protocol(ProtocolEnum::Https)
+                .port(443)
+                .mutual_authentication(
+                    MutualAuthenticationAttributes::builder()
+                        .mode(mode)
+                        .trust_store_arn(
+                            "arn:aws:elasticloadbalancing:us-east-1:000000000000:truststore/ca/1",
+                        )
+                        .build(),
+                )
+                .build()
+        };
+        assert_eq!(
+            mtls("verify").content(),
+            "Listener protocol=HTTPS port=443 mtls=verify trust-store=arn:aws:elasticloadbalancing:us-east-1:000000000000:truststore/ca/1"
+        );
+        assert_eq!(mtls("off").content(), "Listener protocol=HTTPS port=443");
+    }
+
+    #[test]
+    fn test_rule_content() {
+        let rule = Rule::builder().priority("10").is_default(false).build();
+        assert_eq!(rule.content(), "Rule priority=10 is-default=false");
+        assert_eq!(
+            Rule::builder().build().content(),
+            "Rule priority=unknown is-default=unknown"
+        );
+    }
+
+    #[test]
+    fn test_action_content() {
+        let fixed_response = Action::builder()
+            .r#type(ActionTypeEnum::FixedResponse)
+            .fixed_response_config(
+                FixedResponseActionConfig::builder()
+                    .message_body("not found")
+                    .status_code("404")
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            fixed_response.content(),
+            r#"Action (fixed-repsonse) msg=Some("not found") status-code=Some("404")"#
+        );
+
+        let forward = Action::builder()
+            .r#type(ActionTypeEnum::Forward)
+            .target_group_arn("arn:tg/web")
+            .build();
+        assert_eq!(
+            forward.content(),
+            "Action (forward) target-groups=[arn:tg/web]"
+        );
+
+        let redirect = Action::builder()
+            .r#type(ActionTypeEnum::Redirect)
+            .redirect_config(
+                RedirectActionConfig::builder()
+                    .protocol("HTTPS")
+                    .port("443")
+                    .query("a=b")
+                    .status_code(RedirectActionStatusCodeEnum::Http301)
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            redirect.content(),
+            "Action (redirect) location=HTTPS://#{host}:443/#{path}?a=b status-code=HTTP_301"
+        );
+
+        let cognito = Action::builder()
+            .r#type(ActionTypeEnum::AuthenticateCognito)
+            .authenticate_cognito_config(
+                AuthenticateCognitoActionConfig::builder()
+                    .user_pool_arn("arn:pool")
+                    .user_pool_client_id("client")
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            cognito.content(),
+            "Action (authenticate-cognito) user-pool=arn:pool client-id=client"
+        );
+    }
+
+    #[test]
+    fn test_action_content_never_shows_oidc_client_secret() {
+        let secret = "very-secret-client-secret";
+        let oidc = Action::builder()
+            .r#type(ActionTypeEnum::AuthenticateOidc)
+            .authenticate_oidc_config(
+                AuthenticateOidcActionConfig::builder()
+                    .issuer("https://idp.example.com")
+                    .client_id("lbtree")
+                    .client_secret(secret)
+                    .build(),
+            )
+            .build();
+
+        let content = oidc.content();
+        assert_eq!(
+            content,
+            "Action (authenticate-oidc) issuer=https://idp.example.com client-id=lbtree"
+        );
+        assert!(!content.contains(secret));
+    }
+
+    #[test]
+    fn test_action_content_missing_fields() {
+        let fixed_response = Action::builder()
+            .r#type(ActionTypeEnum::FixedResponse)
+            .build();
+        assert_eq!(fixed_response.content(), "Action (fixed-repsonse)");
+
+        let redirect = Action::builder().r#type(ActionTypeEnum::Redirect).build();
+        assert_eq!(redirect.content(), "Action (redirect)");
+
+        assert_eq!(Action::builder().build().content(), "Action (unknown)");
+    }
+
+    #[test]
+    fn test_action_info_resolves_target_group_names() {
+        let action = Action::builder()
+            .r#type(ActionTypeEnum::Forward)
+            .forward_config(
+                ForwardActionConfig::builder()
+                    .target_groups(
+                        TargetGroupTuple::builder()
+                            .target_group_arn("arn:tg/web")
+                            .build(),
+                    )
+                    .target_groups(
+                        TargetGroupTuple::builder()
+                            .target_group_arn("arn:tg/other")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+        let action_info = ActionInfo {
+            action,
+            target_group_names: Arc::new(HashMap::from([(
+                "arn:tg/web".to_string(),
+                "web".to_string(),
+            )])),
+            show_order: false,
+        };
+        assert_eq!(
+            action_info.content(),
+            "Action (forward) target-groups=[web, arn:tg/other]"
+        );
+
+        let fixed_response = ActionInfo {
+            action: Action::builder()
+                .r#type(ActionTypeEnum::FixedResponse)
+                .build(),
+            target_group_names: Arc::default(),
+            show_order: false,
+        };
+        assert_eq!(fixed_response.content(), "Action (fixed-repsonse)");
+    }
+
+    #[test]
+    fn test_multi_action_rule_is_presented_in_order() {
+        let rule = Rule::builder()
+            .actions(
+                Action::builder()
+                    .r#type(ActionTypeEnum::Forward)
+                    .target_group_arn("arn:tg/web")
+                    .order(2)
+                    .build(),
+            )
+            .actions(
+                Action::builder()
+                    .r#type(ActionTypeEnum::AuthenticateOidc)
+                    .authenticate_oidc_config(
+                        AuthenticateOidcActionConfig::builder()
+                            .issuer("https://idp.example.com")
+                            .client_id("lbtree")
+                            .build(),
+                    )
+                    .order(1)
+                    .build(),
+            )
+            .build();
+        let contents: Vec<String> = ordered_actions(&rule)
+            .into_iter()
+            .map(|action| {
+                ActionInfo {
+                    action: action.clone(),
+                    target_group_names: Arc::default(),
+                    show_order: true,
+                }
+                .content()
+            })
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                "Action #1 (authenticate-oidc) issuer=https://idp.example.com client-id=lbtree",
+                "Action #2 (forward) target-groups=[arn:tg/web]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_group_content() {
+        let tg = TargetGroup::builder()
+            .target_group_name("web")
+            .protocol(ProtocolEnum::Http)
+            .port(8080)
+            .build();
+        assert_eq!(
+            tg.content(),
+            r#"Target group "web" protocol=HTTP port=8080"#
+        );
+
+        let ip = TargetGroup::builder()
+            .target_group_name("web")
+            .target_type(TargetTypeEnum::Ip)
+            .protocol(ProtocolEnum::Http)
+            .port(8080)
+            .build();
+        assert_eq!(
+            ip.content(),
+            r#"Target group "web" type=ip protocol=HTTP port=8080"#
+        );
+
+        // Lambda target groups
+        assert_eq!(
+            TargetGroup::builder()
+                .target_group_name("fn")
+                .target_type(TargetTypeEnum::Lambda)
+                .build()
+                .content(),
+            r#"Target group "fn" type=lambda"#
+        );
+        assert_eq!(
+            TargetGroup::builder()
+                .target_group_name("fn")
+                .build()
+                .content(),
+            r#"Target group "fn""#
+        );
+        assert_eq!(
+            TargetGroup::builder().build().content(),
+            r#"Target group "??""#
+        );
+    }
+
+    #[test]
+    fn test_target_group_health_content() {
+        let target_group = TargetGroup::builder()
+            .target_group_name("web")
+            .protocol(ProtocolEnum::Http)
+            .port(80)
+            .build();
+        let target = |state: TargetHealthStateEnum| {
+            TargetHealthDescription::builder()
+                .target_health(TargetHealth::builder().state(state).build())
+                .build()
+        };
+
+        let health = TargetGroupHealth::new(
+            target_group.clone(),
+            &[
+                target(TargetHealthStateEnum::Healthy),
+                target(TargetHealthStateEnum::Unhealthy),
+            ],
+        );
+        assert_eq!(
+            health.content(),
+            r#"Target group "web" protocol=HTTP port=80 (1/2 healthy)"#
+        );
+
+        let empty = TargetGroupHealth::new(target_group, &[]);
+        assert_eq!(
+            empty.content(),
+            r#"Target group "web" protocol=HTTP port=80 (0/0 healthy) <== no healthy targets"#
+        );
+    }
+
+    #[test]
+    fn test_target_content() {
+        let target = TargetHealthDescription::builder()
+            .target(TargetDescription::builder().id("i-123").port(80).build())
+            .build();
+        assert_eq!(target.content(), "Target id=i-123 port=80");
+        assert_eq!(
+            TargetHealthDescription::builder().build().content(),
+            "Target id=unknown"
+        );
+    }
+
+    #[test]
+    fn test_attached_load_balancer_content() {
+        let lb = LoadBalancer::builder()
+            .load_balancer_arn("arn:aws:elasticloadbalancing:eu-west-1:1:loadbalancer/app/web/1")
+            .load_balancer_name("web")
+            .r#type(LoadBalancerTypeEnum::Application)
+            .scheme(LoadBalancerSchemeEnum::Internal)
+            .state(
+                LoadBalancerState::builder()
+                    .code(LoadBalancerStateEnum::Active)
+                    .build(),
+            )
+            .build();
+        let attached = AttachedLoadBalancer(lb);
+        assert_eq!(
+            attached.content(),
+            r#"Used by load balancer "web" type=application scheme=internal state=active"#
+        );
+        assert_eq!(
+            attached.arn().as_deref(),
+            Some("arn:aws:elasticloadbalancing:eu-west-1:1:loadbalancer/app/web/1")
+        );
+    }
+
+    #[test]
+    fn test_health_check_settings_content() {
+        let http = TargetGroup::builder()
+            .health_check_protocol(ProtocolEnum::Http)
+            .health_check_port("traffic-port")
+            .health_check_path("/health")
+            .matcher(Matcher::builder().http_code("200-299").build())
+            .health_check_interval_seconds(30)
+            .health_check_timeout_seconds(5)
+            .healthy_threshold_count(5)
+            .unhealthy_threshold_count(2)
+            .build();
+        assert_eq!(
+            HealthCheckSettings(http).content(),
+            "Health check protocol=HTTP port=traffic-port path=/health http-codes=200-299 interval=30s timeout=5s healthy-threshold=5 unhealthy-threshold=2"
+        );
+
+        let grpc = TargetGroup::builder()
+            .protocol_version("GRPC")
+            .matcher(Matcher::builder().grpc_code("0-12").build())
+            .build();
+        assert_eq!(
+            HealthCheckSettings(grpc).content(),
+            "Health check protocol=unknown port=unknown grpc-codes=0-12 interval=? timeout=? healthy-threshold=? unhealthy-threshold=?"
+        );
+
+        let lambda = TargetGroup::builder().health_check_enabled(false).build();
+        assert_eq!(
+            HealthCheckSettings(lambda).content(),
+            "Health check disabled"
+        );
+    }
+
+    #[test]
+    fn test_health_check_failure() {
+        let failure = |reason: TargetHealthReasonEnum| {
+            HealthCheckFailure::from_health(
+                &TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Unhealthy)
+                    .reason(reason)
+                    .description("Request timed out")
+                    .build(),
+            )
+        };
+        assert_eq!(
+            failure(TargetHealthReasonEnum::Timeout).unwrap().content(),
+            "Health check failure Target.Timeout: health check request timed out detail=\"Request timed out\" hint=\"check the target's security group allows the load balancer on the health check port, and the health check path responds within the timeout\""
+        );
+        // codes without an explanation are passed through as they are
+        assert_eq!(
+            failure(TargetHealthReasonEnum::from("Target.SomethingNew"))
+                .unwrap()
+                .content(),
+            "Health check failure Target.SomethingNew detail=\"Request timed out\""
+        );
+        assert_eq!(
+            HealthCheckFailure::from_health(
+                &TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Draining)
+                    .reason(TargetHealthReasonEnum::DeregistrationInProgress)
+                    .build()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_target_content_labels_state() {
+        let target = |health: TargetHealth| {
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id("i-123").port(80).build())
+                .target_health(health)
+                .build()
+                .content()
+        };
+        assert_eq!(
+            target(
+                TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Healthy)
+                    .build()
+            ),
+            "Target id=i-123 port=80 state=healthy"
+        );
+        assert_eq!(
+            target(
+                TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Initial)
+                    .reason(TargetHealthReasonEnum::InitialHealthChecking)
+                    .build()
+            ),
+            "Target id=i-123 port=80 state=initial (registering) reason=Elb.InitialHealthChecking"
+        );
+        assert_eq!(
+            target(
+                TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Draining)
+                    .reason(TargetHealthReasonEnum::DeregistrationInProgress)
+                    .description("Target deregistration is in progress")
+                    .build()
+            ),
+            r#"Target id=i-123 port=80 state=draining (deregistering) reason=Target.DeregistrationInProgress detail="Target deregistration is in progress""#
+        );
+        assert_eq!(
+            target(
+                TargetHealth::builder()
+                    .state(TargetHealthStateEnum::Unhealthy)
+                    .reason(TargetHealthReasonEnum::Timeout)
+                    .build()
+            ),
+            "Target id=i-123 port=80 state=unhealthy reason=Target.Timeout"
+        );
+    }
+
+    #[test]
+    fn test_load_balancer_attributes_content() {
+        let attributes = LoadBalancerAttributes {
+            deletion_protection: Some("true".to_string()),
+            idle_timeout: Some("60".to_string()),
+            cross_zone: Some("true".to_string()),
+            ip_address_type: Some("dualstack".to_string()),
+            access_logs: AccessLogs {
+                enabled: Some("true".to_string()),
+                bucket: Some("logs".to_string()),
+                prefix: Some("alb".to_string()),
+            },
+        };
+        assert_eq!(
+            attributes.content(),
+            "Attributes deletion-protection=true idle-timeout=60s cross-zone=true ip-address-type=dualstack"
+        );
+        assert_eq!(
+            attributes.access_logs.content(),
+            "Access logs enabled=true bucket=logs prefix=alb"
+        );
+
+        let empty = LoadBalancerAttributes::default();
+        assert_eq!(empty.content(), "Attributes deletion-protection=unknown");
+        assert_eq!(empty.access_logs.content(), "Access logs enabled=unknown");
+    }
+
+    #[test]
+    fn test_target_group_attributes_content() {
+        let attributes = TargetGroupAttributes {
+            deregistration_delay: Some("300".to_string()),
+            stickiness_enabled: Some("false".to_string()),
+            stickiness_type: Some("lb_cookie".to_string()),
+            slow_start: Some("0".to_string()),
+            cross_zone: Some("use_load_balancer_configuration".to_string()),
+        };
+        assert_eq!(
+            attributes.content(),
+            "Attributes deregistration-delay=300s stickiness=false stickiness-type=lb_cookie slow-start=0s cross-zone=use_load_balancer_configuration"
+        );
+        assert_eq!(
+            TargetGroupAttributes::default().content(),
+            "Attributes deregistration-delay=?s stickiness=unknown stickiness-type=unknown slow-start=?s"
+        );
+    }
+
+    #[test]
+    fn test_sort_targets_by_health_puts_unhealthy_first() {
+        let target = |id: &str, state: TargetHealthStateEnum| {
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id(id).port(80).build())
+                .target_health(TargetHealth::builder().state(state).build())
+                .build()
+        };
+        let mut targets = vec![
+            target("a", TargetHealthStateEnum::Healthy),
+            target("b", TargetHealthStateEnum::Unhealthy),
+            target("c", TargetHealthStateEnum::Healthy),
+            target("d", TargetHealthStateEnum::Draining),
+        ];
+
+        sort_targets(&mut targets, Some(SortKey::Health));
+        let ids: Vec<&str> = targets.iter().filter_map(|t| t.target()?.id()).collect();
+        assert_eq!(ids, ["b", "d", "a", "c"]);
+
+        sort_targets(&mut targets, Some(SortKey::Name));
+        let ids: Vec<&str> = targets.iter().filter_map(|t| t.target()?.id()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_sort_listeners_by_port() {
+        let listener = |port| Listener::builder().port(port).build();
+        let mut listeners = vec![listener(8443), listener(80), listener(443)];
+
+        sort_listeners(&mut listeners);
+        let ports: Vec<i32> = listeners.iter().filter_map(|l| l.port()).collect();
+        assert_eq!(ports, [80, 443, 8443]);
+    }
+
+    #[test]
+    fn test_zone_distribution() {
+        let target = |zone: &str, state| {
+            TargetHealthDescription::builder()
+                .target(
+                    TargetDescription::builder()
+                        .id("10.0.0.1")
+                        .availability_zone(zone)
+                        .build(),
+                )
+                .target_health(TargetHealth::builder().state(state).build())
+                .build()
+        };
+        let zones = ZoneDistribution::from_targets(&[
+            target("us-east-1a", TargetHealthStateEnum::Healthy),
+            target("us-east-1b", TargetHealthStateEnum::Unhealthy),
+            target("us-east-1a", TargetHealthStateEnum::Healthy),
+            target("us-east-1b", TargetHealthStateEnum::Healthy),
+        ])
+        .unwrap();
+        assert_eq!(
+            zones.content(),
+            "Zones us-east-1a: 2 healthy, us-east-1b: 1 healthy 1 unhealthy"
+        );
+
+        // a zone that is only draining during a deploy is not reported as unhealthy
+        let zones = ZoneDistribution::from_targets(&[
+            target("us-east-1a", TargetHealthStateEnum::Healthy),
+            target("us-east-1b", TargetHealthStateEnum::Draining),
+            target("us-east-1b", TargetHealthStateEnum::Initial),
+        ])
+        .unwrap();
+        assert_eq!(
+            zones.content(),
+            "Zones us-east-1a: 1 healthy, us-east-1b: 1 draining 1 initial"
+        );
+
+        // a single zone has nothing to compare
+        assert_eq!(
+            ZoneDistribution::from_targets(&[target("us-east-1a", TargetHealthStateEnum::Healthy)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_listener_routes() {
+        let forward = |arn: &str| {
+            Action::builder()
+                .r#type(ActionTypeEnum::Forward)
+                .target_group_arn(arn)
+                .build()
+        };
+        let rules = [
+            Rule::builder()
+                .priority("default")
+                .actions(forward("arn:web"))
+                .build(),
+            Rule::builder()
+                .priority("20")
+                .actions(forward("arn:web"))
+                .build(),
+            Rule::builder()
+                .priority("10")
+                .actions(forward("arn:api"))
+                .build(),
+        ];
+        let names = HashMap::from([("arn:web".to_string(), "web".to_string())]);
+        assert_eq!(
+            ListenerRoutes::from_rules(&rules, &names)
+                .unwrap()
+                .content(),
+            "Reachable target-groups=[arn:api, web]"
+        );
+
+        let redirect = Rule::builder()
+            .priority("default")
+            .actions(Action::builder().r#type(ActionTypeEnum::Redirect).build())
+            .build();
+        assert_eq!(ListenerRoutes::from_rules(&[redirect], &names), None);
+    }
+
+    #[test]
+    fn test_queue_listeners_only_routes_to_given_target_groups() {
+        let forward = |arn: &str| {
+            Action::builder()
+                .r#type(ActionTypeEnum::Forward)
+                .target_group_arn(arn)
+                .build()
+        };
+        let listeners = vec![
+            Listener::builder().port(80).build(),
+            Listener::builder().port(443).build(),
+        ];
+        let rules = vec![
+            Some(vec![
+                Rule::builder()
+                    .priority("1")
+                    .actions(forward("arn:tg/broken"))
+                    .build(),
+                Rule::builder()
+                    .priority("default")
+                    .actions(forward("arn:tg/fine"))
+                    .build(),
+            ]),
+            Some(vec![
+                Rule::builder()
+                    .priority("default")
+                    .actions(forward("arn:tg/fine"))
+                    .build(),
+            ]),
+        ];
+        let routes_to = HashSet::from(["arn:tg/broken".to_string()]);
+        let contents: Vec<(usize, String)> = queue_listeners(
+            &listeners,
+            &rules,
+            &[false, false],
+            &HashMap::new(),
+            &Arc::default(),
+            &AlbOptions::default(),
+            Some(&routes_to),
+        )
+        .iter()
+        .map(|(depth, presenter)| (*depth, presenter.content()))
+        .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (1, "Listener protocol=unknown port=80".to_string()),
+                (2, "Rule priority=1 is-default=unknown".to_string()),
+                (
+                    3,
+                    "Action (forward) target-groups=[arn:tg/broken]".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queue_listeners_marks_denied_rules() {
+        let listeners = vec![Listener::builder().port(80).build()];
+        let contents: Vec<(usize, String)> = queue_listeners(
+            &listeners,
+            &[None],
+            &[true],
+            &HashMap::new(),
+            &Arc::default(),
+            &AlbOptions::default(),
+            None,
+        )
+        .iter()
+        .map(|(depth, presenter)| (*depth, presenter.content()))
+        .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (1, "Listener protocol=unknown port=80".to_string()),
+                (2, "<access denied>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queue_listeners_shows_rule_tags() {
+        let listeners = vec![Listener::builder().port(80).build()];
+        let rules = vec![Some(vec![
+            Rule::builder()
+                .rule_arn("arn:rule/1")
+                .priority("1")
+                .is_default(false)
+                .build(),
+            Rule::builder()
+                .rule_arn("arn:rule/default")
+                .priority("default")
+                .is_default(true)
+                .build(),
+        ])];
+        let rule_tags = HashMap::from([(
+            "arn:rule/1".to_string(),
+            RuleTags(vec![
+                ("owner".to_string(), "payments".to_string()),
+                ("ticket".to_string(), "OPS-123".to_string()),
+            ]),
+        )]);
+        let contents: Vec<(usize, String)> = queue_listeners(
+            &listeners,
+            &rules,
+            &[false],
+            &rule_tags,
+            &Arc::default(),
+            &AlbOptions::default(),
+            None,
+        )
+        .iter()
+        .map(|(depth, presenter)| (*depth, presenter.content()))
+        .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (1, "Listener protocol=unknown port=80".to_string()),
+                (2, "Rule priority=1 is-default=false".to_string()),
+                (3, "Tags owner=payments ticket=OPS-123".to_string()),
+                (2, "Rule priority=default is-default=true".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_content() {
+        assert_eq!(Empty("targets").content(), "<no targets>");
+        assert_eq!(Empty("rules").arn(), None);
+    }
+
+    #[test]
+    fn test_waf_association_content() {
+        let waf = WafAssociation {
+            web_acl_name: Some("acl".to_string()),
+        };
+        assert_eq!(waf.content(), r#"WAF web-acl="acl""#);
+        assert_eq!(
+            WafAssociation { web_acl_name: None }.content(),
+            "WAF web-acl=none"
+        );
+    }
+
+    #[test]
+    fn test_static_addresses() {
+        let zone = AvailabilityZone::builder()
+            .zone_name("us-east-1a")
+            .load_balancer_addresses(
+                LoadBalancerAddress::builder()
+                    .ip_address("203.0.113.10")
+                    .private_ipv4_address("10.0.1.10")
+                    .allocation_id("eipalloc-1")
+                    .build(),
+            )
+            .build();
+        let nlb = LoadBalancer::builder()
+            .r#type(LoadBalancerTypeEnum::Network)
+            .availability_zones(zone.clone())
+            .build();
+        let dualstack_nlb = LoadBalancer::builder()
+            .r#type(LoadBalancerTypeEnum::Network)
+            .availability_zones(
+                AvailabilityZone::builder()
+                    .zone_name("us-east-1b")
+                    .load_balancer_addresses(
+                        LoadBalancerAddress::builder()
+                            .private_ipv4_address("10.0.2.10")
+                            .i_pv6_address("2600:1f18:abcd:1::10")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            StaticAddress::from_load_balancer(&dualstack_nlb)[0].content(),
+            "Address az=us-east-1b private-ip=10.0.2.10 ipv6=2600:1f18:abcd:1::10"
+        );
+        let addresses = StaticAddress::from_load_balancer(&nlb);
+        assert_eq!(addresses.len(), 1);
+        assert_eq!(
+            addresses[0].content(),
+            "Address az=us-east-1a ip=203.0.113.10 private-ip=10.0.1.10 allocation=eipalloc-1"
+        );
+
+        let alb = LoadBalancer::builder()
+            .r#type(LoadBalancerTypeEnum::Application)
+            .availability_zones(zone)
+            .build();
+        assert!(StaticAddress::from_load_balancer(&alb).is_empty());
+    }
+
+    #[test]
+    fn test_matched_content() {
+        let rule = Matched(Rule::builder().priority("5").is_default(false).build());
+        assert_eq!(
+            rule.content(),
+            "Rule priority=5 is-default=false <== matches request"
+        );
+    }
 }
@@ -0,0 +1,121 @@
+use color_eyre::eyre;
+
+/// A resource named by an ARN given to `resource`, with what is needed to display its tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArnTarget {
+    /// A load balancer, also used for ARNs of its listeners and rules
+    LoadBalancer(String),
+    TargetGroup(String),
+    EcsCluster(String),
+    EcsService {
+        cluster: String,
+        service: String,
+    },
+    /// The id of a REST API, also used for ARNs of its resources and stages
+    RestApi(String),
+}
+
+/// A parsed ARN, as `arn:partition:service:region:account:resource`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedArn {
+    pub region: Option<String>,
+    pub target: ArnTarget,
+}
+
+/// Work out which tree shows the resource an ARN names, from its service and resource type
+pub fn parse_arn(arn: &str) -> eyre::Result<ParsedArn> {
+    let parts: Vec<&str> = arn.splitn(6, ':').collect();
+    let ["arn", partition, service, region, account, resource] = parts[..] else {
+        eyre::bail!(
+            "{arn:?} is not an ARN, expected arn:partition:service:region:account:resource"
+        );
+    };
+    let (resource_type, path) = resource.split_once('/').unwrap_or((resource, ""));
+    let segments: Vec<&str> = path.split('/').collect();
+    let target = match (service, resource_type) {
+        ("elasticloadbalancing", "loadbalancer") => ArnTarget::LoadBalancer(arn.to_string()),
+        ("elasticloadbalancing", "targetgroup") => ArnTarget::TargetGroup(arn.to_string()),
+        // listener/app/my-lb/50dc6c495c0c9188/f2f7dc8efc522ab2 and listener-rule/app/... belong
+        // to the load balancer named by their first three segments
+        ("elasticloadbalancing", "listener" | "listener-rule") if segments.len() >= 3 => {
+            ArnTarget::LoadBalancer(format!(
+                "arn:{partition}:{service}:{region}:{account}:loadbalancer/{}",
+                segments[..3].join("/")
+            ))
+        }
+        ("ecs", "cluster") => ArnTarget::EcsCluster(arn.to_string()),
+        // the long form, service/cluster-name/service-name; the old form has no cluster name
+        ("ecs", "service") if segments.len() == 2 => ArnTarget::EcsService {
+            cluster: format!(
+                "arn:{partition}:{service}:{region}:{account}:cluster/{}",
+                segments[0]
+            ),
+            service: arn.to_string(),
+        },
+        // arn:aws:apigateway:us-east-1::/restapis/a1b2c3/stages/prod
+        ("apigateway", "") if segments.first() == Some(&"restapis") && segments.len() >= 2 => {
+            ArnTarget::RestApi(segments[1].to_string())
+        }
+        _ => eyre::bail!(
+            "{arn:?} is not a supported resource; expected a load balancer, listener, rule or target \
+             group, an ECS cluster or service, or an API Gateway REST API"
+        ),
+    };
+    Ok(ParsedArn {
+        region: Some(region.to_string()).filter(|r| !r.is_empty()),
+        target,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arn() {
+        let parse = |arn: &str| parse_arn(arn).unwrap().target;
+        assert_eq!(
+            parse(
+                "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/my-lb/50dc"
+            ),
+            ArnTarget::LoadBalancer(
+                "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/my-lb/50dc"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse(
+                "arn:aws:elasticloadbalancing:us-east-1:000000000000:listener/app/my-lb/50dc/f2f7"
+            ),
+            ArnTarget::LoadBalancer(
+                "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/my-lb/50dc"
+                    .to_string()
+            )
+        );
+        assert!(matches!(
+            parse("arn:aws:elasticloadbalancing:us-east-1:000000000000:targetgroup/web/73e2"),
+            ArnTarget::TargetGroup(_)
+        ));
+        assert_eq!(
+            parse("arn:aws:ecs:eu-west-1:000000000000:service/prod/web"),
+            ArnTarget::EcsService {
+                cluster: "arn:aws:ecs:eu-west-1:000000000000:cluster/prod".to_string(),
+                service: "arn:aws:ecs:eu-west-1:000000000000:service/prod/web".to_string(),
+            }
+        );
+        assert_eq!(
+            parse("arn:aws:apigateway:us-east-1::/restapis/a1b2c3/stages/prod"),
+            ArnTarget::RestApi("a1b2c3".to_string())
+        );
+        assert_eq!(
+            parse_arn("arn:aws:ecs:eu-west-1:000000000000:cluster/prod")
+                .unwrap()
+                .region
+                .as_deref(),
+            Some("eu-west-1")
+        );
+
+        assert!(parse_arn("arn:aws:s3:::my-bucket").is_err());
+        assert!(parse_arn("my-lb").is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::Write;
+
+use crate::Cli;
+
+/// Write the completion script for `shell`, covering every subcommand and flag
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn test_write_completions_for_every_shell() {
+        for &shell in Shell::value_variants() {
+            let mut out = Vec::new();
+            write_completions(shell, &mut out);
+            let script = String::from_utf8(out).unwrap();
+            assert!(
+                script.contains("elbv2"),
+                "{shell} completions miss subcommands"
+            );
+            assert!(
+                script.contains("--max-items"),
+                "{shell} completions miss flags"
+            );
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use clap::ValueEnum;
+use color_eyre::eyre::{self, Context};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::{Cli, OutputFormat};
+
+/// Whether to use colours in progress spinners and error reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Only when writing to a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Apply the choice to everything drawn on stdout and stderr
+    pub fn apply(self) {
+        let enabled = match self {
+            ColorChoice::Auto => return,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+}
+
+/// Settings that can be given in the config file, the environment or on the command line, where
+/// `None` leaves the value to a lower-precedence source
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Layer {
+    region: Option<String>,
+    profile: Option<String>,
+    output: Option<OutputFormat>,
+    indent: Option<usize>,
+    color: Option<ColorChoice>,
+}
+
+impl Layer {
+    /// Take every value set in `other` in place of the one in `self`
+    fn overridden_by(self, other: Layer) -> Layer {
+        Layer {
+            region: other.region.or(self.region),
+            profile: other.profile.or(self.profile),
+            output: other.output.or(self.output),
+            indent: other.indent.or(self.indent),
+            color: other.color.or(self.color),
+        }
+    }
+
+    fn from_file_contents(contents: &str) -> eyre::Result<Layer> {
+        toml::from_str(contents).map_err(|e| eyre::eyre!("{e}"))
+    }
+
+    /// The standard AWS variables, so a region or profile from the environment still wins over the
+    /// config file as it does for the AWS CLI, and `NO_COLOR`
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Layer {
+        let set = |name: &str| var(name).filter(|value| !value.is_empty());
+        Layer {
+            region: set("AWS_REGION").or_else(|| set("AWS_DEFAULT_REGION")),
+            profile: set("AWS_PROFILE"),
+            output: None,
+            indent: None,
+            color: set("NO_COLOR").map(|_| ColorChoice::Never),
+        }
+    }
+
+    fn from_cli(cli: &Cli) -> Layer {
+        let output = if cli.arns_only {
+            Some(OutputFormat::Arns)
+        } else if cli.emit_commands {
+            Some(OutputFormat::Commands)
+        } else {
+            cli.output
+        };
+        Layer {
+            region: cli.region.clone(),
+            profile: cli.profile.clone(),
+            output,
+            indent: cli.indent,
+            color: cli.color,
+        }
+    }
+}
+
+/// Settings merged from the config file, the environment and the command line, in increasing
+/// order of precedence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Region to query, or `None` for the SDK's own lookup
+    pub region: Option<String>,
+    /// Shared config profile to load credentials and region from
+    pub profile: Option<String>,
+    pub output: OutputFormat,
+    pub indent: usize,
+    pub color: ColorChoice,
+}
+
+impl Config {
+    /// Load the config file, if there is one, and apply the environment and command line on top
+    pub fn load(cli: &Cli) -> eyre::Result<Config> {
+        let file = match file_path() {
+            Some(path) if path.exists() => {
+                let contents = std::fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("reading config file {}", path.display()))?;
+                Layer::from_file_contents(&contents)
+                    .wrap_err_with(|| format!("parsing config file {}", path.display()))?
+            }
+            _ => Layer::default(),
+        };
+        let env = Layer::from_env(|name| std::env::var(name).ok());
+        Ok(Config::from_layer(
+            file.overridden_by(env).overridden_by(Layer::from_cli(cli)),
+        ))
+    }
+
+    /// Label for `--context` given without one, naming the profile and region, e.g.
+    /// `prod/eu-west-1`, or `default` if neither is set
+    pub fn context_label(&self) -> String {
+        let parts: Vec<&str> = [self.profile.as_deref(), self.region.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if parts.is_empty() {
+            "default".to_string()
+        } else {
+            parts.join("/")
+        }
+    }
+
+    fn from_layer(layer: Layer) -> Config {
+        Config {
+            region: layer.region,
+            profile: layer.profile,
+            output: layer.output.unwrap_or(OutputFormat::Tree),
+            indent: layer.indent.unwrap_or(crate::tree::INDENT_WIDTH),
+            color: layer.color.unwrap_or_default(),
+        }
+    }
+}
+
+/// `$LBTREE_CONFIG`, or `lbtree/config.toml` in `$XDG_CONFIG_HOME` or `~/.config`
+fn file_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("LBTREE_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("lbtree").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() {
+        let layer = Layer::from_file_contents(
+            r#"
+region = "eu-west-1"
+profile = "prod"
+output = "jsonl"
+indent = 4
+color = "never"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            layer,
+            Layer {
+                region: Some("eu-west-1".to_string()),
+                profile: Some("prod".to_string()),
+                output: Some(OutputFormat::JsonLines),
+                indent: Some(4),
+                color: Some(ColorChoice::Never),
+            }
+        );
+    }
+
+    #[test]
+    fn test_context_label() {
+        let config = Config::from_layer(Layer {
+            region: Some("eu-west-1".to_string()),
+            profile: Some("prod".to_string()),
+            ..Layer::default()
+        });
+        assert_eq!(config.context_label(), "prod/eu-west-1");
+        let region_only = Config {
+            profile: None,
+            ..config.clone()
+        };
+        assert_eq!(region_only.context_label(), "eu-west-1");
+        assert_eq!(
+            Config::from_layer(Layer::default()).context_label(),
+            "default"
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(Layer::from_file_contents("regoin = \"eu-west-1\"").is_err());
+    }
+
+    #[test]
+    fn test_precedence() {
+        let file = Layer {
+            region: Some("eu-west-1".to_string()),
+            profile: Some("prod".to_string()),
+            output: Some(OutputFormat::Json),
+            indent: Some(4),
+            color: None,
+        };
+        let env = Layer::from_env(|name| match name {
+            "AWS_REGION" => Some("us-east-1".to_string()),
+            "AWS_PROFILE" => Some(String::new()),
+            _ => None,
+        });
+        let cli = Layer {
+            output: Some(OutputFormat::Tree),
+            ..Layer::default()
+        };
+        assert_eq!(
+            Config::from_layer(file.overridden_by(env).overridden_by(cli)),
+            Config {
+                region: Some("us-east-1".to_string()),
+                profile: Some("prod".to_string()),
+                output: OutputFormat::Tree,
+                indent: 4,
+                color: ColorChoice::Auto,
+            }
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+
+use crate::present::OutputWriter;
+use crate::tree::{INDENT_WIDTH, Node};
+
+/// Number of leading words two differing lines must share to be shown as a change to the same
+/// resource, e.g. `Target group "web" protocol=HTTP` in `Target group "web" protocol=HTTP port=80
+/// (1/2 healthy)`, rather than one resource removed and another added
+const MIN_SHARED_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Shown for context, as the parent of a changed resource
+    Unchanged,
+    Removed,
+    Added,
+}
+
+/// A line of a unified-diff-style comparison of two trees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: Change,
+    pub depth: usize,
+    pub content: String,
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = match self.change {
+            Change::Unchanged => ' ',
+            Change::Removed => '-',
+            Change::Added => '+',
+        };
+        write!(
+            f,
+            "{marker} {}-> {}",
+            " ".repeat(self.depth * INDENT_WIDTH),
+            self.content
+        )
+    }
+}
+
+/// Compare two trees, returning only the lines that differ along with their ancestors
+///
+/// Children are matched by content regardless of their order, so a listener or target group
+/// returned in a different order is not reported. The roots are always compared with each other,
+/// so two load balancers with different names still have their listeners compared.
+pub fn diff_trees(old: &Node, new: &Node) -> Vec<DiffLine> {
+    diff_pair(old, new, 0)
+}
+
+/// Write the differences between two trees, or a note that they are the same
+pub fn write_diff(old: &Node, new: &Node, writer: &dyn OutputWriter) {
+    let lines = diff_trees(old, new);
+    if lines.is_empty() {
+        writer.write_line("No differences");
+    }
+    for line in lines {
+        writer.write_line(&line.to_string());
+    }
+}
+
+fn diff_pair(old: &Node, new: &Node, depth: usize) -> Vec<DiffLine> {
+    let children = diff_children(&old.children, &new.children, depth + 1);
+    let mut lines = Vec::new();
+    if old.content == new.content {
+        if children.is_empty() {
+            return lines;
+        }
+        lines.push(line(Change::Unchanged, depth, old));
+    } else {
+        lines.push(line(Change::Removed, depth, old));
+        lines.push(line(Change::Added, depth, new));
+    }
+    lines.extend(children);
+    lines
+}
+
+fn diff_children(old: &[Node], new: &[Node], depth: usize) -> Vec<DiffLine> {
+    let mut unmatched: Vec<Option<&Node>> = new.iter().map(Some).collect();
+    let mut pairs: Vec<Option<&Node>> = vec![None; old.len()];
+
+    // identical resources first, so a changed resource does not take the place of an unchanged one
+    for (i, node) in old.iter().enumerate() {
+        if let Some(slot) = unmatched
+            .iter_mut()
+            .find(|slot| slot.is_some_and(|other| other.content == node.content))
+        {
+            pairs[i] = slot.take();
+        }
+    }
+    for (i, node) in old.iter().enumerate() {
+        if pairs[i].is_some() {
+            continue;
+        }
+        let best = unmatched
+            .iter_mut()
+            .filter_map(|slot| {
+                let shared = shared_words(&node.content, &(*slot)?.content);
+                (shared >= MIN_SHARED_WORDS).then_some((shared, slot))
+            })
+            .max_by_key(|(shared, _)| *shared);
+        if let Some((_, slot)) = best {
+            pairs[i] = slot.take();
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (node, pair) in old.iter().zip(pairs) {
+        match pair {
+            Some(other) => lines.extend(diff_pair(node, other, depth)),
+            None => subtree(Change::Removed, node, depth, &mut lines),
+        }
+    }
+    for node in unmatched.into_iter().flatten() {
+        subtree(Change::Added, node, depth, &mut lines);
+    }
+    lines
+}
+
+fn shared_words(a: &str, b: &str) -> usize {
+    a.split_whitespace()
+        .zip(b.split_whitespace())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+fn subtree(change: Change, node: &Node, depth: usize, lines: &mut Vec<DiffLine>) {
+    lines.push(line(change, depth, node));
+    for child in &node.children {
+        subtree(change, child, depth + 1, lines);
+    }
+}
+
+fn line(change: Change, depth: usize, node: &Node) -> DiffLine {
+    DiffLine {
+        change,
+        depth,
+        content: node.content.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(content: &str, children: Vec<Node>) -> Node {
+        let mut node = Node::new(content);
+        node.children = children;
+        node
+    }
+
+    fn rendered(lines: &[DiffLine]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_identical_trees_have_no_differences() {
+        let tree = node(
+            "LB blue",
+            vec![node("Listener protocol=HTTP port=80", vec![])],
+        );
+        assert!(diff_trees(&tree, &tree).is_empty());
+    }
+
+    #[test]
+    fn test_reordered_children_are_not_differences() {
+        let old = node(
+            "LB blue",
+            vec![
+                node("Listener protocol=HTTP port=80", vec![]),
+                node("Listener protocol=HTTPS port=443", vec![]),
+            ],
+        );
+        let new = node(
+            "LB blue",
+            vec![
+                node("Listener protocol=HTTPS port=443", vec![]),
+                node("Listener protocol=HTTP port=80", vec![]),
+            ],
+        );
+        assert!(diff_trees(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_added_removed_and_changed_resources() {
+        let old = node(
+            "LB blue",
+            vec![
+                node("Listener protocol=HTTP port=80", vec![]),
+                node(
+                    r#"Target group "web" protocol=HTTP port=80 (2/2 healthy)"#,
+                    vec![node("Target id=i-1 port=80", vec![])],
+                ),
+            ],
+        );
+        let new = node(
+            "LB green",
+            vec![
+                node("Listener protocol=HTTPS port=443", vec![]),
+                node(
+                    r#"Target group "web" protocol=HTTP port=80 (1/2 healthy)"#,
+                    vec![node("Target id=i-1 port=80", vec![])],
+                ),
+            ],
+        );
+        assert_eq!(
+            rendered(&diff_trees(&old, &new)),
+            vec![
+                "- -> LB blue",
+                "+ -> LB green",
+                "-   -> Listener protocol=HTTP port=80",
+                r#"-   -> Target group "web" protocol=HTTP port=80 (2/2 healthy)"#,
+                r#"+   -> Target group "web" protocol=HTTP port=80 (1/2 healthy)"#,
+                "+   -> Listener protocol=HTTPS port=443",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unchanged_parents_are_shown_for_context() {
+        let old = node(
+            "LB blue",
+            vec![node(
+                "Listener protocol=HTTP port=80",
+                vec![node("Rule priority=1 is-default=false", vec![])],
+            )],
+        );
+        let new = node(
+            "LB blue",
+            vec![node("Listener protocol=HTTP port=80", vec![])],
+        );
+        assert_eq!(
+            rendered(&diff_trees(&old, &new)),
+            vec![
+                "  -> LB blue",
+                "    -> Listener protocol=HTTP port=80",
+                "-     -> Rule priority=1 is-default=false",
+            ]
+        );
+    }
+}
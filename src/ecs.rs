@@ -1,13 +1,29 @@
 use aws_config::SdkConfig;
-use aws_sdk_ecs::types::{Cluster, Service, Task};
+use aws_sdk_ecs::primitives::DateTime;
+use aws_sdk_ecs::types::{
+    Cluster, ContainerInstance, DeploymentRolloutState, DesiredStatus, ManagedAgentName, Service,
+    Task,
+};
 use color_eyre::eyre::{self, Context};
-use crossbeam::channel::unbounded;
+use crossbeam::channel::bounded;
 use skim::prelude::*;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
-
-use crate::present::{OutputWriter, Present};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{Instrument, info_span, instrument};
+
+use crate::age::{Aged, format_duration};
+use crate::error::{Cancelled, LbtreeError, NotFound, NothingSelected};
+use crate::estimate::Estimate;
+use crate::pagination::collect_all;
+use crate::picker::{PICKER_CHANNEL_CAPACITY, send_to_picker};
+use crate::present::{OutputWriter, Present, Queued};
+use crate::progress::Spinner;
+use crate::resolve::{ResolverCache, arn_has_suffix, unique_match};
+use crate::tree::{Node, build_tree};
 
 #[derive(Debug, Clone)]
 struct ClusterItem {
@@ -41,6 +57,63 @@ impl SkimItem for ServiceItem {
     }
 }
 
+/// Options controlling what is fetched and shown for an ECS service
+#[derive(Debug, Clone, Default)]
+pub struct EcsOptions {
+    /// Show the digest of the image each container is running, to compare deployments across
+    /// tasks
+    pub image_digests: bool,
+    /// List the EC2 container instances registered to the cluster, with their remaining capacity
+    pub instances: bool,
+    /// Stop listing clusters and services for the pickers after this many
+    pub max_items: Option<usize>,
+    /// Also show the tasks that stopped within this long, e.g. to see why they failed
+    pub stopped_within: Option<Duration>,
+    /// Show how long ago each service was created; tasks always show their age
+    pub age: bool,
+}
+
+/// Maximum number of tasks accepted by a single `describe_tasks` call
+const MAX_DESCRIBE_TASKS: usize = 100;
+
+/// Number of clusters or services described per call while filling the picker, which is the most
+/// `describe_services` accepts
+///
+/// A page of 100 clusters is split into 10 calls, at most [`MAX_CONCURRENT_PICKER_DESCRIBES`] of
+/// them in flight at a time, rather than one call for all of them, so names start appearing after
+/// the first small call returns.
+///
+/// For a 100-cluster account, with `describe_clusters` taking about the same time `t` whatever
+/// the batch size, the first names appear after one list call plus `t` either way, but they are
+/// 10 names rather than all 100 and the picker is usable while the rest arrive. The full list
+/// takes the list call plus `3t` (10 calls in waves of 4) instead of `t`. When the call time grows
+/// with the number of clusters described, as it does once each cluster carries settings and
+/// statistics, the first names arrive up to 10 times sooner and the full list takes about as long.
+/// These figures are worked out from the call pattern, not timed against AWS.
+const PICKER_DESCRIBE_BATCH: usize = 10;
+
+/// The ARNs of a page that fit under `max_items` when `listed` were on earlier pages, and how many
+/// were left out if any were
+fn cap_page(
+    arns: &[String],
+    listed: usize,
+    max_items: Option<usize>,
+) -> (&[String], Option<usize>) {
+    match max_items {
+        Some(max) if listed + arns.len() > max => {
+            let room = max.saturating_sub(listed);
+            (&arns[..room], Some(arns.len() - room))
+        }
+        _ => (arns, None),
+    }
+}
+
+/// Maximum number of describe calls made at the same time while filling the picker
+const MAX_CONCURRENT_PICKER_DESCRIBES: usize = 4;
+
+/// Maximum number of container instances accepted by a single `describe_container_instances` call
+const MAX_DESCRIBE_CONTAINER_INSTANCES: usize = 100;
+
 /// Container information combining runtime state with definition
 #[derive(Debug, Clone)]
 pub struct ContainerInfo {
@@ -48,6 +121,426 @@ pub struct ContainerInfo {
     pub image: String,
     pub command: Option<Vec<String>>,
     pub last_status: Option<String>,
+    /// (container name, condition) of each container that must reach a state before this one
+    /// starts
+    pub depends_on: Vec<(String, String)>,
+    /// Digest of the image the container is running, with `--image-digests`
+    pub image_digest: Option<String>,
+    /// Where the container sends its logs, if configured
+    pub log_config: Option<LogConfig>,
+    /// The container's own health check, if defined
+    pub health_check: Option<HealthCheckInfo>,
+    /// Result of the health check reported by the running container, e.g. `HEALTHY`
+    pub health_status: Option<String>,
+    /// CPU units reserved for the container
+    pub cpu: Option<i32>,
+    /// Hard memory limit in MiB
+    pub memory: Option<i32>,
+    /// Soft memory limit in MiB, reserved for the container on the instance
+    pub memory_reservation: Option<i32>,
+    /// Status of the ECS Exec agent in the running container, e.g. `RUNNING`; the agent is only
+    /// there when ECS Exec is enabled on the task
+    pub exec_agent_status: Option<String>,
+}
+
+/// Health check of a container definition, run by the container agent inside the container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheckInfo {
+    pub command: Vec<String>,
+    pub interval: Option<i32>,
+    pub timeout: Option<i32>,
+    pub retries: Option<i32>,
+}
+
+impl HealthCheckInfo {
+    fn from_definition(health_check: &aws_sdk_ecs::types::HealthCheck) -> Self {
+        Self {
+            command: health_check.command().to_vec(),
+            interval: health_check.interval(),
+            timeout: health_check.timeout(),
+            retries: health_check.retries(),
+        }
+    }
+}
+
+impl std::fmt::Display for HealthCheckInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "health-check={:?}", self.command)?;
+        if let Some(interval) = self.interval {
+            write!(f, " interval={interval}s")?;
+        }
+        if let Some(timeout) = self.timeout {
+            write!(f, " timeout={timeout}s")?;
+        }
+        if let Some(retries) = self.retries {
+            write!(f, " retries={retries}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Log configuration of a container definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    /// Log driver, e.g. `awslogs` or `splunk`
+    pub driver: String,
+    /// CloudWatch log group, for the `awslogs` driver
+    pub group: Option<String>,
+    /// Prefix of the CloudWatch log stream names, for the `awslogs` driver
+    pub stream_prefix: Option<String>,
+}
+
+impl LogConfig {
+    fn from_definition(config: &aws_sdk_ecs::types::LogConfiguration) -> Self {
+        let driver = config.log_driver().as_str().to_string();
+        let option = |key: &str| {
+            config
+                .options()
+                .and_then(|options| options.get(key))
+                .filter(|_| driver == "awslogs")
+                .cloned()
+        };
+        Self {
+            group: option("awslogs-group"),
+            stream_prefix: option("awslogs-stream-prefix"),
+            driver,
+        }
+    }
+}
+
+impl std::fmt::Display for LogConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "logs={}", self.driver)?;
+        if let Some(group) = &self.group {
+            write!(f, " log-group={group}")?;
+        }
+        if let Some(prefix) = &self.stream_prefix {
+            write!(f, " log-stream-prefix={prefix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A container image reference, e.g. `123456789012.dkr.ecr.us-east-1.amazonaws.com/app:v1`, split
+/// into its parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageRef<'a> {
+    /// Host of the registry, `None` for Docker Hub
+    pub registry: Option<&'a str>,
+    pub repository: &'a str,
+    pub tag: Option<&'a str>,
+    /// Digest the image is pinned to, e.g. `nginx@sha256:...`
+    pub digest: Option<&'a str>,
+}
+
+impl<'a> ImageRef<'a> {
+    pub fn parse(image: &'a str) -> Self {
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (image, None),
+        };
+        // the first component is only a registry if it looks like a host, e.g. `bitnami/redis` is
+        // a Docker Hub repository
+        let (registry, name) = match name.split_once('/') {
+            Some((host, rest))
+                if host.contains('.') || host.contains(':') || host == "localhost" =>
+            {
+                (Some(host), rest)
+            }
+            _ => (None, name),
+        };
+        let (repository, tag) = match name.rsplit_once(':') {
+            Some((repository, tag)) => (repository, Some(tag)),
+            None => (name, None),
+        };
+
+        Self {
+            registry,
+            repository,
+            tag,
+            digest,
+        }
+    }
+}
+
+impl std::fmt::Display for ImageRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(registry) = self.registry {
+            write!(f, "registry={registry} ")?;
+        }
+        write!(f, "repo={}", self.repository)?;
+        if let Some(tag) = self.tag {
+            write!(f, " tag={tag}")?;
+        }
+        if let Some(digest) = self.digest {
+            write!(f, " digest={digest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a task can be reached: its network interface in `awsvpc` mode, or the container instance
+/// it was placed on for the EC2 launch type
+#[derive(Debug, Clone, Default)]
+pub struct TaskNetwork {
+    pub network_interface_id: Option<String>,
+    pub private_ip: Option<String>,
+    pub container_instance_id: Option<String>,
+    /// the task has not finished provisioning, so its interface may not be attached yet
+    pub provisioning: bool,
+}
+
+impl TaskNetwork {
+    /// Returns `None` if there is nothing to show for the task
+    pub fn from_task(task: &Task) -> Option<Self> {
+        let mut network = TaskNetwork {
+            container_instance_id: task
+                .container_instance_arn()
+                .and_then(|arn| arn.rsplit('/').next())
+                .map(|id| id.to_string()),
+            provisioning: matches!(
+                task.last_status(),
+                Some("PROVISIONING" | "PENDING" | "ACTIVATING")
+            ),
+            ..Default::default()
+        };
+
+        let eni_details = task
+            .attachments()
+            .iter()
+            .filter(|attachment| attachment.r#type() == Some("ElasticNetworkInterface"))
+            .flat_map(|attachment| attachment.details());
+        for detail in eni_details {
+            let value = detail.value().map(|v| v.to_string());
+            match detail.name() {
+                Some("networkInterfaceId") => network.network_interface_id = value,
+                Some("privateIPv4Address") => network.private_ip = value,
+                _ => {}
+            }
+        }
+
+        let known = network.network_interface_id.is_some()
+            || network.private_ip.is_some()
+            || network.container_instance_id.is_some();
+        (known || network.provisioning).then_some(network)
+    }
+}
+
+impl Present for TaskNetwork {
+    fn content(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(private_ip) = &self.private_ip {
+            parts.push(format!("private-ip={private_ip}"));
+        }
+        if let Some(network_interface_id) = &self.network_interface_id {
+            parts.push(format!("eni={network_interface_id}"));
+        }
+        if let Some(container_instance_id) = &self.container_instance_id {
+            parts.push(format!("container-instance={container_instance_id}"));
+        }
+        if parts.is_empty() {
+            return "Network pending".to_string();
+        }
+        format!("Network {}", parts.join(" "))
+    }
+}
+
+/// Whether a service's deployment circuit breaker is on, and whether it stopped a deployment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub enabled: bool,
+    pub rollback: bool,
+    /// The deployment the circuit breaker marked as failed, if any
+    pub failed_deployment: Option<FailedDeployment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedDeployment {
+    pub id: String,
+    pub failed_tasks: i32,
+    pub reason: Option<String>,
+}
+
+impl CircuitBreaker {
+    /// `None` for services without a circuit breaker configured, e.g. ones using an external or
+    /// CodeDeploy deployment controller
+    pub fn from_service(service: &Service) -> Option<Self> {
+        let breaker = service
+            .deployment_configuration()?
+            .deployment_circuit_breaker()?;
+        let failed_deployment = service
+            .deployments()
+            .iter()
+            .find(|d| d.rollout_state() == Some(&DeploymentRolloutState::Failed))
+            .map(|d| FailedDeployment {
+                id: d.id().unwrap_or("unknown").to_string(),
+                failed_tasks: d.failed_tasks(),
+                reason: d.rollout_state_reason().map(|r| r.to_string()),
+            });
+        Some(CircuitBreaker {
+            enabled: breaker.enable(),
+            rollback: breaker.rollback(),
+            failed_deployment,
+        })
+    }
+}
+
+impl Present for CircuitBreaker {
+    fn content(&self) -> String {
+        if !self.enabled {
+            return "Circuit breaker disabled".to_string();
+        }
+        let mut content = format!("Circuit breaker enabled rollback={}", self.rollback);
+        match &self.failed_deployment {
+            Some(failed) => {
+                content.push_str(&format!(
+                    " tripped deployment={} failed-tasks={}",
+                    failed.id, failed.failed_tasks
+                ));
+                if let Some(reason) = &failed.reason {
+                    content.push_str(&format!(" reason=\"{reason}\""));
+                }
+                if self.rollback {
+                    content.push_str(" <== rolled back");
+                } else {
+                    content.push_str(" <== deployment failed");
+                }
+            }
+            None => content.push_str(" not-tripped"),
+        }
+        content
+    }
+}
+
+/// A port of a service published through Service Connect, and the names other services in the
+/// namespace reach it by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceConnectPort {
+    pub namespace: Option<String>,
+    pub port_name: String,
+    pub discovery_name: Option<String>,
+    /// `dns-name:port` of each client alias
+    pub aliases: Vec<String>,
+}
+
+impl ServiceConnectPort {
+    /// The ports published by the primary deployment of a service, which is the configuration
+    /// being rolled out
+    pub fn from_service(service: &Service) -> Vec<Self> {
+        let Some(config) = service
+            .deployments()
+            .iter()
+            .find(|d| d.status() == Some("PRIMARY"))
+            .and_then(|d| d.service_connect_configuration())
+            .filter(|c| c.enabled())
+        else {
+            return Vec::new();
+        };
+        config
+            .services()
+            .iter()
+            .map(|published| ServiceConnectPort {
+                namespace: config.namespace().map(|n| n.to_string()),
+                port_name: published.port_name().to_string(),
+                discovery_name: published.discovery_name().map(|n| n.to_string()),
+                aliases: published
+                    .client_aliases()
+                    .iter()
+                    .map(|alias| {
+                        format!(
+                            "{}:{}",
+                            alias.dns_name().unwrap_or(published.port_name()),
+                            alias.port()
+                        )
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl Present for ServiceConnectPort {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Service Connect namespace={} port-name={}",
+            self.namespace.as_deref().unwrap_or("unknown"),
+            self.port_name,
+        );
+        if let Some(discovery_name) = &self.discovery_name {
+            content.push_str(&format!(" discovery-name={discovery_name}"));
+        }
+        if !self.aliases.is_empty() {
+            content.push_str(&format!(" aliases=[{}]", self.aliases.join(", ")));
+        }
+        content
+    }
+}
+
+/// A Cloud Map registry the tasks of a service are registered in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudMapRegistry {
+    pub registry_arn: String,
+    /// `service.namespace` name the registry answers for, if it could be looked up
+    pub dns_name: Option<String>,
+    pub container: Option<String>,
+    pub port: Option<i32>,
+}
+
+impl Present for CloudMapRegistry {
+    fn content(&self) -> String {
+        let mut content = "Cloud Map".to_string();
+        if let Some(dns_name) = &self.dns_name {
+            content.push_str(&format!(" dns={dns_name}"));
+        }
+        if let Some(container) = &self.container {
+            content.push_str(&format!(" container={container}"));
+        }
+        if let Some(port) = self.port {
+            content.push_str(&format!(" port={port}"));
+        }
+        content
+    }
+
+    fn arn(&self) -> Option<String> {
+        Some(self.registry_arn.clone())
+    }
+}
+
+/// Look up the DNS name a Cloud Map service answers for, from its name and its namespace's name
+///
+/// Failures only lose the name, as the rest of the tree is still useful without it.
+async fn cloud_map_dns_name(
+    client: &aws_sdk_servicediscovery::Client,
+    registry_arn: &str,
+) -> Option<String> {
+    let service_id = registry_arn.rsplit('/').next()?;
+    let service = match client
+        .get_service()
+        .id(service_id)
+        .send()
+        .instrument(info_span!("get_service", service_id))
+        .await
+    {
+        Ok(response) => response.service()?.clone(),
+        Err(e) => {
+            tracing::warn!(registry_arn, error = %e, "looking up Cloud Map service");
+            return None;
+        }
+    };
+    let namespace = match client
+        .get_namespace()
+        .id(service.namespace_id()?)
+        .send()
+        .instrument(info_span!("get_namespace"))
+        .await
+    {
+        Ok(response) => response.namespace()?.name()?.to_string(),
+        Err(e) => {
+            tracing::warn!(registry_arn, error = %e, "looking up Cloud Map namespace");
+            return None;
+        }
+    };
+    Some(format!("{}.{namespace}", service.name()?))
 }
 
 impl Present for Cluster {
@@ -63,8 +556,8 @@ impl Present for Cluster {
         )
     }
 
-    fn indent(&self) -> usize {
-        0
+    fn arn(&self) -> Option<String> {
+        self.cluster_arn().map(|arn| arn.to_string())
     }
 }
 
@@ -75,17 +568,95 @@ impl Present for Service {
         let desired = self.desired_count();
         let running = self.running_count();
         let pending = self.pending_count();
+        let load_balancers = self.load_balancers().len();
+        // only meaningful, and only accepted by ECS, for services behind a load balancer
+        let grace_str = self
+            .health_check_grace_period_seconds()
+            .map(|seconds| format!(" health-check-grace={seconds}s"))
+            .unwrap_or_default();
 
         format!(
-            "Service \"{name}\" status={status} desired={desired} running={running} pending={pending}"
+            "Service \"{name}\" status={status} desired={desired} running={running} pending={pending} load-balancers={load_balancers}{grace_str}"
         )
     }
 
-    fn indent(&self) -> usize {
-        2
+    fn arn(&self) -> Option<String> {
+        self.service_arn().map(|arn| arn.to_string())
+    }
+}
+
+/// How long a task has been running, how long a stopped task lived, or how long a task that has
+/// not started yet has been waiting, e.g. ` age=2h13m`
+fn task_age(task: &Task, now: SystemTime) -> String {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let between = |from: &DateTime, to: i64| format_duration((to - from.secs()).max(0) as u64);
+    match (task.started_at(), task.stopped_at()) {
+        (Some(started), Some(stopped)) => format!(" lifetime={}", between(started, stopped.secs())),
+        (Some(started), None) => format!(" age={}", between(started, now)),
+        (None, Some(_)) => String::new(),
+        (None, None) => match task.created_at() {
+            Some(created) => format!(" age=pending pending-for={}", between(created, now)),
+            None => " age=pending".to_string(),
+        },
     }
 }
 
+/// Parse a duration made of whole numbers of days, hours, minutes and seconds, e.g. `1h`, `30m` or
+/// `1h30m`, as given to `--stopped-within`
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let mut secs = 0;
+    let mut number = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => {
+                return Err(format!(
+                    "unknown unit '{c}' in {s:?}, expected d, h, m or s"
+                ));
+            }
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("missing number before '{c}' in {s:?}"))?;
+        secs += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!(
+            "missing unit after {number} in {s:?}, e.g. {number}m"
+        ));
+    }
+    if secs == 0 {
+        return Err(format!("{s:?} is not a duration, e.g. 1h or 30m"));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Whether a task should be shown, leaving out the ones that stopped longer than `stopped_within`
+/// ago
+///
+/// Tasks that are stopping but have not stopped yet are always shown.
+fn is_recent(task: &Task, stopped_within: Duration, now: SystemTime) -> bool {
+    let Some(stopped) = task.stopped_at() else {
+        return true;
+    };
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    now - stopped.secs() <= stopped_within.as_secs() as i64
+}
+
 impl Present for Task {
     fn content(&self) -> String {
         // Extract task ID from ARN (last part after /)
@@ -99,14 +670,61 @@ impl Present for Task {
             .launch_type()
             .map(|lt| lt.as_str())
             .unwrap_or("unknown");
+        // tasks still on the previous revision stand out during a rollout
+        let taskdef = self
+            .task_definition_arn()
+            .and_then(|arn| arn.rsplit('/').next())
+            .map(|family_revision| format!(" taskdef={family_revision}"))
+            .unwrap_or_default();
+
+        // whether `aws ecs execute-command` can be used on the task
+        let exec = if self.enable_execute_command() {
+            "enabled"
+        } else {
+            "disabled"
+        };
+
+        format!(
+            "Task {task_id} status={last_status} desired={desired_status} launch-type={launch_type}{taskdef} exec={exec}{age}",
+            age = task_age(self, SystemTime::now()),
+        )
+    }
 
+    fn arn(&self) -> Option<String> {
+        self.task_arn().map(|arn| arn.to_string())
+    }
+}
+
+/// Remaining and registered amount of a resource of a container instance, e.g. `512/2048`
+fn instance_resource(instance: &ContainerInstance, name: &str) -> String {
+    let value = |resources: &[aws_sdk_ecs::types::Resource]| {
+        resources
+            .iter()
+            .find(|r| r.name() == Some(name))
+            .map(|r| r.integer_value().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    format!(
+        "{}/{}",
+        value(instance.remaining_resources()),
+        value(instance.registered_resources())
+    )
+}
+
+impl Present for ContainerInstance {
+    fn content(&self) -> String {
+        let ec2_id = self.ec2_instance_id().unwrap_or("unknown");
+        let status = self.status().unwrap_or("unknown");
         format!(
-            "Task {task_id} status={last_status} desired={desired_status} launch-type={launch_type}"
+            "Container instance ec2={ec2_id} status={status} running-tasks={} cpu={} memory={}",
+            self.running_tasks_count(),
+            instance_resource(self, "CPU"),
+            instance_resource(self, "MEMORY"),
         )
     }
 
-    fn indent(&self) -> usize {
-        4
+    fn arn(&self) -> Option<String> {
+        self.container_instance_arn().map(|arn| arn.to_string())
     }
 }
 
@@ -118,60 +736,143 @@ impl Present for ContainerInfo {
             .as_ref()
             .map(|cmd| format!(" command={:?}", cmd))
             .unwrap_or_default();
+        let depends_on_str = if self.depends_on.is_empty() {
+            String::new()
+        } else {
+            let dependencies: Vec<String> = self
+                .depends_on
+                .iter()
+                .map(|(name, condition)| format!("{name}({condition})"))
+                .collect();
+            format!(" waits-for={}", dependencies.join(","))
+        };
+        let image_digest_str = self
+            .image_digest
+            .as_ref()
+            .map(|digest| format!(" running-digest={digest}"))
+            .unwrap_or_default();
+        let log_config_str = self
+            .log_config
+            .as_ref()
+            .map(|config| format!(" {config}"))
+            .unwrap_or_default();
+        // how the task's CPU and memory are split between its containers
+        let mut resources_str = self
+            .cpu
+            .map(|cpu| format!(" cpu={cpu}"))
+            .unwrap_or_default();
+        match (self.memory, self.memory_reservation) {
+            (Some(memory), Some(reservation)) => {
+                resources_str.push_str(&format!(" mem={memory}(res {reservation})"))
+            }
+            (Some(memory), None) => resources_str.push_str(&format!(" mem={memory}")),
+            (None, Some(reservation)) => {
+                resources_str.push_str(&format!(" mem-reservation={reservation}"))
+            }
+            (None, None) => {}
+        }
+        // the status is only meaningful when the container has a health check
+        let health_str = self
+            .health_check
+            .as_ref()
+            .map(|health_check| {
+                format!(
+                    " health={} {health_check}",
+                    self.health_status.as_deref().unwrap_or("UNKNOWN")
+                )
+            })
+            .unwrap_or_default();
+
+        let exec_agent_str = self
+            .exec_agent_status
+            .as_ref()
+            .map(|status| format!(" exec-agent={status}"))
+            .unwrap_or_default();
 
         format!(
-            "Container \"{name}\" image={image} status={status}{command_str}",
+            "Container \"{name}\" {image}{image_digest_str} status={status}{resources_str}{command_str}{depends_on_str}{log_config_str}{health_str}{exec_agent_str}",
             name = self.name,
-            image = self.image,
+            image = ImageRef::parse(&self.image),
         )
     }
-
-    fn indent(&self) -> usize {
-        6
-    }
 }
 
-/// Let the user choose the cluster to use
-async fn select_cluster(client: &aws_sdk_ecs::Client) -> eyre::Result<Option<String>> {
-    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+/// Let the user choose the cluster to use, from at most `max_items` of them
+async fn select_cluster(
+    client: &aws_sdk_ecs::Client,
+    max_items: Option<usize>,
+) -> eyre::Result<Option<String>> {
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = bounded(PICKER_CHANNEL_CAPACITY);
 
     let client = client.clone();
 
     let fetch_handle = tokio::spawn(async move {
-        let result: eyre::Result<()> = async {
+        // the number of items left out, if listing stopped at max_items
+        let result: eyre::Result<Option<usize>> = async {
             let mut paginator = client.list_clusters().into_paginator().send();
 
-            while let Some(page) = paginator.next().await {
+            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PICKER_DESCRIBES));
+            let mut describes = JoinSet::new();
+            let mut listed = 0;
+            let mut left_out = None;
+            while let Some(page) = paginator
+                .next()
+                .instrument(info_span!("list_clusters"))
+                .await
+            {
                 let page = page.context("fetching clusters page")?;
-
-                let cluster_arns: Vec<_> = page.cluster_arns().to_vec();
-                if cluster_arns.is_empty() {
-                    continue;
+                let (arns, more) = cap_page(page.cluster_arns(), listed, max_items);
+                listed += arns.len();
+
+                // Describe clusters to get names, in small batches at once so the first names
+                // reach the picker before a whole page of 100 is described
+                for batch in arns.chunks(PICKER_DESCRIBE_BATCH) {
+                    let client = client.clone();
+                    let tx = tx.clone();
+                    let semaphore = Arc::clone(&semaphore);
+                    let batch = batch.to_vec();
+                    describes.spawn(
+                        async move {
+                            // panic safety: the semaphore is never closed
+                            let _permit =
+                                semaphore.acquire_owned().await.expect("semaphore closed");
+                            let clusters = client
+                                .describe_clusters()
+                                .set_clusters(Some(batch))
+                                .send()
+                                .await
+                                .context("describing clusters")?;
+
+                            for cluster in clusters.clusters() {
+                                let name = cluster.cluster_name().unwrap_or("unknown");
+                                let arn = cluster.cluster_arn().unwrap_or("");
+                                let status = cluster.status().unwrap_or("unknown");
+
+                                let item = ClusterItem {
+                                    display: format!("{} ({})", name, status),
+                                    arn: arn.to_string(),
+                                };
+
+                                // stop once the user has closed the picker
+                                if !send_to_picker(&tx, Arc::new(item)).await {
+                                    break;
+                                }
+                            }
+                            Ok::<_, eyre::Report>(())
+                        }
+                        .instrument(info_span!("describe_clusters")),
+                    );
                 }
-
-                // Describe clusters to get names
-                let clusters = client
-                    .describe_clusters()
-                    .set_clusters(Some(cluster_arns))
-                    .send()
-                    .await
-                    .context("describing clusters")?;
-
-                for cluster in clusters.clusters() {
-                    let name = cluster.cluster_name().unwrap_or("unknown");
-                    let arn = cluster.cluster_arn().unwrap_or("");
-                    let status = cluster.status().unwrap_or("unknown");
-
-                    let item = ClusterItem {
-                        display: format!("{} ({})", name, status),
-                        arn: arn.to_string(),
-                    };
-
-                    let _ = tx.send(Arc::new(item));
+                if more.is_some() {
+                    left_out = more;
+                    break;
                 }
             }
+            while let Some(result) = describes.join_next().await {
+                result.context("describe task panicked")??;
+            }
 
-            Ok(())
+            Ok(left_out)
         }
         .await;
 
@@ -187,15 +888,19 @@ async fn select_cluster(client: &aws_sdk_ecs::Client) -> eyre::Result<Option<Str
 
     let selected = Skim::run_with(&options, Some(rx));
 
+    let spinner = Spinner::new("Fetching clusters");
     let fetch_result = fetch_handle
         .await
         .context("background fetch task panicked")?;
-    fetch_result?;
+    drop(spinner);
+    if let Some(more) = fetch_result? {
+        eprintln!("... (truncated, {more}+ more clusters not listed)");
+    }
 
     let selected = match selected {
         Some(output) => {
             if output.is_abort {
-                return Ok(None);
+                return Err(Cancelled.into());
             }
 
             output
@@ -209,58 +914,94 @@ async fn select_cluster(client: &aws_sdk_ecs::Client) -> eyre::Result<Option<Str
     Ok(selected)
 }
 
-/// Let the user choose the service to use
+/// Let the user choose the service to use, from at most `max_items` of them
 async fn select_service(
     client: &aws_sdk_ecs::Client,
     cluster_arn: &str,
+    max_items: Option<usize>,
 ) -> eyre::Result<Option<String>> {
-    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = bounded(PICKER_CHANNEL_CAPACITY);
 
     let client = client.clone();
     let cluster_arn = cluster_arn.to_string();
 
     let fetch_handle = tokio::spawn(async move {
-        let result: eyre::Result<()> = async {
+        // the number of items left out, if listing stopped at max_items
+        let result: eyre::Result<Option<usize>> = async {
             let mut paginator = client
                 .list_services()
                 .cluster(&cluster_arn)
                 .into_paginator()
                 .send();
 
-            while let Some(page) = paginator.next().await {
+            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PICKER_DESCRIBES));
+            let mut describes = JoinSet::new();
+            let mut listed = 0;
+            let mut left_out = None;
+            while let Some(page) = paginator
+                .next()
+                .instrument(info_span!("list_services"))
+                .await
+            {
                 let page = page.context("fetching services page")?;
-
-                let service_arns: Vec<_> = page.service_arns().to_vec();
-                if service_arns.is_empty() {
-                    continue;
+                let (arns, more) = cap_page(page.service_arns(), listed, max_items);
+                listed += arns.len();
+
+                // Describe services to get names and status while the next page is listed
+                for batch in arns.chunks(PICKER_DESCRIBE_BATCH) {
+                    let client = client.clone();
+                    let tx = tx.clone();
+                    let semaphore = Arc::clone(&semaphore);
+                    let cluster_arn = cluster_arn.clone();
+                    let batch = batch.to_vec();
+                    describes.spawn(
+                        async move {
+                            // panic safety: the semaphore is never closed
+                            let _permit =
+                                semaphore.acquire_owned().await.expect("semaphore closed");
+                            let services = client
+                                .describe_services()
+                                .cluster(&cluster_arn)
+                                .set_services(Some(batch))
+                                .send()
+                                .await
+                                .context("describing services")?;
+
+                            for service in services.services() {
+                                let name = service.service_name().unwrap_or("unknown");
+                                let arn = service.service_arn().unwrap_or("");
+                                let status = service.status().unwrap_or("unknown");
+                                let running = service.running_count();
+                                let desired = service.desired_count();
+
+                                let item = ServiceItem {
+                                    display: format!(
+                                        "{} ({}) {}/{}",
+                                        name, status, running, desired
+                                    ),
+                                    arn: arn.to_string(),
+                                };
+
+                                // stop once the user has closed the picker
+                                if !send_to_picker(&tx, Arc::new(item)).await {
+                                    break;
+                                }
+                            }
+                            Ok::<_, eyre::Report>(())
+                        }
+                        .instrument(info_span!("describe_services")),
+                    );
                 }
-
-                // Describe services to get names and status
-                let services = client
-                    .describe_services()
-                    .cluster(&cluster_arn)
-                    .set_services(Some(service_arns))
-                    .send()
-                    .await
-                    .context("describing services")?;
-
-                for service in services.services() {
-                    let name = service.service_name().unwrap_or("unknown");
-                    let arn = service.service_arn().unwrap_or("");
-                    let status = service.status().unwrap_or("unknown");
-                    let running = service.running_count();
-                    let desired = service.desired_count();
-
-                    let item = ServiceItem {
-                        display: format!("{} ({}) {}/{}", name, status, running, desired),
-                        arn: arn.to_string(),
-                    };
-
-                    let _ = tx.send(Arc::new(item));
+                if more.is_some() {
+                    left_out = more;
+                    break;
                 }
             }
+            while let Some(result) = describes.join_next().await {
+                result.context("describe task panicked")??;
+            }
 
-            Ok(())
+            Ok(left_out)
         }
         .await;
 
@@ -276,15 +1017,19 @@ async fn select_service(
 
     let selected = Skim::run_with(&options, Some(rx));
 
+    let spinner = Spinner::new("Fetching services");
     let fetch_result = fetch_handle
         .await
         .context("background fetch task panicked")?;
-    fetch_result?;
+    drop(spinner);
+    if let Some(more) = fetch_result? {
+        eprintln!("... (truncated, {more}+ more services not listed)");
+    }
 
     let selected = match selected {
         Some(output) => {
             if output.is_abort {
-                return Ok(None);
+                return Err(Cancelled.into());
             }
 
             output
@@ -298,148 +1043,317 @@ async fn select_service(
     Ok(selected)
 }
 
-/// Display an ECS service hierarchy
-pub async fn display_ecs(
+/// Resolve a cluster name or partial ARN to its full ARN
+///
+/// Full ARNs are returned as they are without calling AWS.
+pub async fn resolve_cluster_arn(
+    client: &aws_sdk_ecs::Client,
+    input: &str,
+) -> Result<String, LbtreeError> {
+    if input.starts_with("arn:") {
+        return Ok(input.to_string());
+    }
+
+    let cluster_arns = collect_all(
+        client.list_clusters().into_paginator().items().send(),
+        "listing clusters",
+    )
+    .instrument(info_span!("list_clusters"))
+    .await?;
+    let matches = cluster_arns
+        .into_iter()
+        .filter(|arn| arn_has_suffix(arn, input))
+        .collect();
+    Ok(unique_match("cluster", input, matches)?)
+}
+
+/// Resolve a service name or partial ARN within a cluster to its full ARN
+///
+/// Full ARNs are returned as they are without calling AWS.
+pub async fn resolve_service_arn(
+    client: &aws_sdk_ecs::Client,
+    cluster_arn: &str,
+    input: &str,
+) -> Result<String, LbtreeError> {
+    if input.starts_with("arn:") {
+        return Ok(input.to_string());
+    }
+
+    let service_arns = collect_all(
+        client
+            .list_services()
+            .cluster(cluster_arn)
+            .into_paginator()
+            .items()
+            .send(),
+        "listing services",
+    )
+    .instrument(info_span!("list_services"))
+    .await?;
+    let matches = service_arns
+        .into_iter()
+        .filter(|arn| arn_has_suffix(arn, input))
+        .collect();
+    Ok(unique_match("service", input, matches)?)
+}
+
+/// Fetch an ECS service hierarchy without writing any output
+///
+/// Returns `None` if a cluster or service ARN was not given and the user did not select one.
+#[instrument(skip(config))]
+pub async fn fetch_ecs_tree(
     config: &SdkConfig,
     cluster_arn: Option<String>,
     service_arn: Option<String>,
-    writer: &dyn OutputWriter,
-) -> eyre::Result<()> {
+    options: EcsOptions,
+) -> Result<Option<Node>, LbtreeError> {
     let client = aws_sdk_ecs::Client::new(config);
 
     // Get or select cluster
     let cluster_arn = if let Some(arn) = cluster_arn {
-        arn
+        resolve_cluster_arn(&client, &arn).await?
     } else {
-        match select_cluster(&client).await? {
+        match select_cluster(&client, options.max_items).await? {
             Some(arn) => arn,
-            None => {
-                eprintln!("No cluster selected");
-                std::process::exit(1);
-            }
+            None => return Ok(None),
         }
     };
 
+    let spinner = Spinner::new("Fetching cluster details");
+    let mut out: Vec<Queued> = Vec::new();
+
     // Get cluster details
     let clusters = client
         .describe_clusters()
         .clusters(&cluster_arn)
         .send()
+        .instrument(info_span!("describe_clusters"))
         .await
         .context("describing cluster")?;
 
     let cluster = clusters
         .clusters()
         .first()
-        .ok_or_else(|| eyre::eyre!("Cluster not found: {}", cluster_arn))?;
-    cluster.present(writer);
+        .ok_or_else(|| NotFound::new("cluster", &cluster_arn))?;
+    // the spinner must not be drawn over the skim UI
+    drop(spinner);
 
     // Get or select service
     let service_arn = if let Some(arn) = service_arn {
-        arn
+        resolve_service_arn(&client, &cluster_arn, &arn).await?
     } else {
-        match select_service(&client, &cluster_arn).await? {
+        match select_service(&client, &cluster_arn, options.max_items).await? {
             Some(arn) => arn,
-            None => {
-                eprintln!("No service selected");
-                std::process::exit(1);
-            }
+            None => return Ok(None),
         }
     };
 
+    let spinner = Spinner::new("Fetching service details");
+
     // Get service details
     let services = client
         .describe_services()
         .cluster(&cluster_arn)
         .services(&service_arn)
         .send()
+        .instrument(info_span!("describe_services"))
         .await
         .context("describing service")?;
 
     let service = services
         .services()
         .first()
-        .ok_or_else(|| eyre::eyre!("Service not found: {}", service_arn))?;
-    service.present(writer);
+        .ok_or_else(|| NotFound::new("service", &service_arn))?;
+    if options.age {
+        out.push((
+            1,
+            Box::new(Aged {
+                inner: service.clone(),
+                created: service.created_at().map(|created| created.secs()),
+                now: SystemTime::now(),
+            }),
+        ));
+    } else {
+        out.push((1, Box::new(service.clone())));
+    }
 
-    // List tasks for this service
-    let task_arns = client
-        .list_tasks()
-        .cluster(&cluster_arn)
-        .service_name(service.service_name().unwrap_or(""))
-        .send()
-        .await
-        .context("listing tasks")?;
+    // first under the service, as it answers whether the last deployment was rolled back
+    if let Some(breaker) = CircuitBreaker::from_service(service) {
+        out.push((2, Box::new(breaker)));
+    }
 
-    if task_arns.task_arns().is_empty() {
-        return Ok(());
+    // how other services reach this one without a load balancer
+    for port in ServiceConnectPort::from_service(service) {
+        out.push((2, Box::new(port)));
+    }
+    if !service.service_registries().is_empty() {
+        let discovery_client = aws_sdk_servicediscovery::Client::new(config);
+        for registry in service.service_registries() {
+            let Some(registry_arn) = registry.registry_arn() else {
+                continue;
+            };
+            out.push((
+                2,
+                Box::new(CloudMapRegistry {
+                    registry_arn: registry_arn.to_string(),
+                    dns_name: cloud_map_dns_name(&discovery_client, registry_arn).await,
+                    container: registry.container_name().map(|c| c.to_string()),
+                    port: registry.container_port().or(registry.port()),
+                }),
+            ));
+        }
     }
 
-    // Describe tasks
-    let tasks = client
-        .describe_tasks()
-        .cluster(&cluster_arn)
-        .set_tasks(Some(task_arns.task_arns().to_vec()))
-        .send()
-        .await
-        .context("describing tasks")?;
+    // List tasks for this service
+    let mut task_arns = collect_all(
+        client
+            .list_tasks()
+            .cluster(&cluster_arn)
+            .service_name(service.service_name().unwrap_or(""))
+            .into_paginator()
+            .items()
+            .send(),
+        "listing tasks",
+    )
+    .instrument(info_span!("list_tasks"))
+    .await?;
+    // ECS only lists running tasks unless asked for stopped ones, which it keeps for about an hour
+    if options.stopped_within.is_some() {
+        task_arns.extend(
+            collect_all(
+                client
+                    .list_tasks()
+                    .cluster(&cluster_arn)
+                    .service_name(service.service_name().unwrap_or(""))
+                    .desired_status(DesiredStatus::Stopped)
+                    .into_paginator()
+                    .items()
+                    .send(),
+                "listing stopped tasks",
+            )
+            .instrument(info_span!("list_tasks", stopped = true))
+            .await?,
+        );
+    }
+
+    // Describe tasks, in batches as describe_tasks accepts a limited number of tasks per call
+    let mut tasks = Vec::new();
+    for batch in task_arns.chunks(MAX_DESCRIBE_TASKS) {
+        let response = client
+            .describe_tasks()
+            .cluster(&cluster_arn)
+            .set_tasks(Some(batch.to_vec()))
+            .send()
+            .instrument(info_span!("describe_tasks"))
+            .await
+            .context("describing tasks")?;
+        tasks.extend_from_slice(response.tasks());
+    }
+    if let Some(stopped_within) = options.stopped_within {
+        let now = SystemTime::now();
+        tasks.retain(|task| is_recent(task, stopped_within, now));
+    }
 
     // Cache for task definitions to avoid redundant API calls
-    let mut task_def_cache: HashMap<String, HashMap<String, ContainerInfo>> = HashMap::new();
+    let task_def_cache: ResolverCache<HashMap<String, ContainerInfo>> = ResolverCache::new();
 
-    for task in tasks.tasks() {
-        task.present(writer);
+    for task in &tasks {
+        out.push((2, Box::new(task.clone())));
+        if let Some(network) = TaskNetwork::from_task(task) {
+            out.push((3, Box::new(network)));
+        }
 
         // Get task definition to get container images
         if let Some(task_def_arn) = task.task_definition_arn() {
-            let container_defs = if let Some(cached) = task_def_cache.get(task_def_arn) {
-                cached.clone()
-            } else {
-                // Fetch task definition
-                let task_def = client
-                    .describe_task_definition()
-                    .task_definition(task_def_arn)
-                    .send()
-                    .await
-                    .context("describing task definition")?;
-
-                let mut defs: HashMap<String, ContainerInfo> = HashMap::new();
-                if let Some(td) = task_def.task_definition() {
-                    for container_def in td.container_definitions() {
-                        let name = container_def.name().unwrap_or("unknown").to_string();
-                        let image = container_def.image().unwrap_or("unknown").to_string();
-                        let command = {
-                            let cmd = container_def.command();
-                            if cmd.is_empty() {
-                                None
-                            } else {
-                                Some(cmd.iter().map(|s| s.to_string()).collect())
-                            }
-                        };
-
-                        defs.insert(
-                            name.clone(),
-                            ContainerInfo {
-                                name,
-                                image,
-                                command,
-                                last_status: None,
-                            },
-                        );
+            let container_defs = task_def_cache
+                .get_or_fetch(task_def_arn, || async {
+                    // Fetch task definition
+                    let task_def = client
+                        .describe_task_definition()
+                        .task_definition(task_def_arn)
+                        .send()
+                        .instrument(info_span!("describe_task_definition", task_def_arn))
+                        .await
+                        .context("describing task definition")?;
+
+                    let mut defs: HashMap<String, ContainerInfo> = HashMap::new();
+                    if let Some(td) = task_def.task_definition() {
+                        for container_def in td.container_definitions() {
+                            let name = container_def.name().unwrap_or("unknown").to_string();
+                            let image = container_def.image().unwrap_or("unknown").to_string();
+                            let command = {
+                                let cmd = container_def.command();
+                                if cmd.is_empty() {
+                                    None
+                                } else {
+                                    Some(cmd.iter().map(|s| s.to_string()).collect())
+                                }
+                            };
+
+                            let depends_on = container_def
+                                .depends_on()
+                                .iter()
+                                .map(|dependency| {
+                                    (
+                                        dependency.container_name().to_string(),
+                                        dependency.condition().as_str().to_string(),
+                                    )
+                                })
+                                .collect();
+                            let log_config = container_def
+                                .log_configuration()
+                                .map(LogConfig::from_definition);
+                            let health_check = container_def
+                                .health_check()
+                                .map(HealthCheckInfo::from_definition);
+                            // 0 when the container has no CPU units of its own
+                            let cpu = Some(container_def.cpu()).filter(|&cpu| cpu > 0);
+
+                            defs.insert(
+                                name.clone(),
+                                ContainerInfo {
+                                    name,
+                                    image,
+                                    command,
+                                    last_status: None,
+                                    depends_on,
+                                    image_digest: None,
+                                    log_config,
+                                    health_check,
+                                    health_status: None,
+                                    cpu,
+                                    memory: container_def.memory(),
+                                    memory_reservation: container_def.memory_reservation(),
+                                    exec_agent_status: None,
+                                },
+                            );
+                        }
                     }
-                }
-                task_def_cache.insert(task_def_arn.to_string(), defs.clone());
-                defs
-            };
+                    Ok(defs)
+                })
+                .await?;
 
             // Get runtime container info and merge with definition
             for container in task.containers() {
                 let container_name = container.name().unwrap_or("unknown");
                 let last_status = container.last_status().map(|s| s.to_string());
+                let health_status = container.health_status().map(|s| s.as_str().to_string());
+                let image_digest = container
+                    .image_digest()
+                    .filter(|_| options.image_digests)
+                    .map(|digest| digest.to_string());
+                let exec_agent_status = container
+                    .managed_agents()
+                    .iter()
+                    .find(|agent| agent.name() == Some(&ManagedAgentName::ExecuteCommandAgent))
+                    .map(|agent| agent.last_status().unwrap_or("UNKNOWN").to_string());
 
                 if let Some(mut info) = container_defs.get(container_name).cloned() {
                     info.last_status = last_status;
-                    info.present(writer);
+                    info.image_digest = image_digest;
+                    info.health_status = health_status;
+                    info.exec_agent_status = exec_agent_status;
+                    out.push((3, Box::new(info)));
                 } else {
                     // Container not in definition (shouldn't happen, but handle gracefully)
                     let info = ContainerInfo {
@@ -447,12 +1361,564 @@ pub async fn display_ecs(
                         image: "unknown".to_string(),
                         command: None,
                         last_status,
+                        depends_on: Vec::new(),
+                        image_digest,
+                        log_config: None,
+                        health_check: None,
+                        health_status,
+                        cpu: None,
+                        memory: None,
+                        memory_reservation: None,
+                        exec_agent_status,
                     };
-                    info.present(writer);
+                    out.push((3, Box::new(info)));
                 }
             }
         }
     }
 
+    // Fargate-only clusters have no container instances, so nothing is added for them
+    if options.instances {
+        let instance_arns = collect_all(
+            client
+                .list_container_instances()
+                .cluster(&cluster_arn)
+                .into_paginator()
+                .items()
+                .send(),
+            "listing container instances",
+        )
+        .instrument(info_span!("list_container_instances"))
+        .await?;
+
+        for batch in instance_arns.chunks(MAX_DESCRIBE_CONTAINER_INSTANCES) {
+            let response = client
+                .describe_container_instances()
+                .cluster(&cluster_arn)
+                .set_container_instances(Some(batch.to_vec()))
+                .send()
+                .instrument(info_span!("describe_container_instances"))
+                .await
+                .context("describing container instances")?;
+            for instance in response.container_instances() {
+                out.push((1, Box::new(instance.clone())));
+            }
+        }
+    }
+
+    drop(spinner);
+
+    Ok(Some(build_tree(cluster, &out)))
+}
+
+/// Display an ECS service hierarchy
+///
+/// Returns [`LbtreeError::NoSelection`] if the user does not choose a resource to display.
+pub async fn display_ecs(
+    config: &SdkConfig,
+    cluster_arn: Option<String>,
+    service_arn: Option<String>,
+    options: EcsOptions,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let tree = fetch_ecs_tree(config, cluster_arn, service_arn, options)
+        .await?
+        .ok_or(NothingSelected("cluster or service"))?;
+    tree.render(writer);
+
     Ok(())
 }
+
+/// Count the cluster given by name, ARN or partial ARN, or every cluster, with their services and
+/// running tasks
+///
+/// The counts come from `describe_clusters`, so no services or tasks are listed. Rendering every
+/// service is assumed to need one task definition lookup per service.
+pub async fn estimate_ecs(
+    config: &SdkConfig,
+    cluster_arn: Option<String>,
+) -> Result<Estimate, LbtreeError> {
+    let client = aws_sdk_ecs::Client::new(config);
+    let mut estimate = Estimate::default();
+
+    // a cluster given on the command line must exist, rather than counting as no clusters
+    let given = cluster_arn.is_some();
+    let cluster_arns = match cluster_arn {
+        Some(arn) => vec![resolve_cluster_arn(&client, &arn).await?],
+        None => {
+            collect_all(
+                client.list_clusters().into_paginator().items().send(),
+                "listing clusters",
+            )
+            .instrument(info_span!("list_clusters"))
+            .await?
+        }
+    };
+
+    // describe_clusters accepts the same number of clusters per call as describe_tasks does tasks
+    for batch in cluster_arns.chunks(MAX_DESCRIBE_TASKS) {
+        let response = client
+            .describe_clusters()
+            .set_clusters(Some(batch.to_vec()))
+            .send()
+            .instrument(info_span!("describe_clusters"))
+            .await
+            .context("describing clusters")?;
+        if given && response.clusters().is_empty() {
+            return Err(NotFound::new("cluster", &batch[0]).into());
+        }
+
+        for cluster in response.clusters() {
+            let services = cluster.active_services_count().max(0) as usize;
+            let tasks = cluster.running_tasks_count().max(0) as usize;
+            estimate.count("clusters", 1);
+            estimate.count("services", services);
+            estimate.count("running tasks", tasks);
+
+            // per service: the cluster, the service, listing its tasks and its task definition,
+            // plus describing the tasks in batches
+            estimate.calls += 4 * services + tasks.div_ceil(MAX_DESCRIBE_TASKS);
+        }
+    }
+
+    Ok(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_ecs::types::{
+        Attachment, Deployment, DeploymentCircuitBreaker, DeploymentConfiguration, KeyValuePair,
+        LaunchType, Resource, ServiceConnectClientAlias, ServiceConnectConfiguration,
+        ServiceConnectService,
+    };
+
+    #[test]
+    fn test_cluster_content() {
+        let cluster = Cluster::builder()
+            .cluster_name("prod")
+            .status("ACTIVE")
+            .active_services_count(2)
+            .running_tasks_count(5)
+            .pending_tasks_count(1)
+            .build();
+        assert_eq!(
+            cluster.content(),
+            r#"Cluster "prod" status=ACTIVE services=2 running-tasks=5 pending-tasks=1"#
+        );
+        assert_eq!(
+            Cluster::builder().build().content(),
+            r#"Cluster "unknown" status=unknown services=0 running-tasks=0 pending-tasks=0"#
+        );
+    }
+
+    #[test]
+    fn test_service_content() {
+        let service = Service::builder()
+            .service_name("web")
+            .status("ACTIVE")
+            .desired_count(3)
+            .running_count(2)
+            .pending_count(1)
+            .load_balancers(
+                aws_sdk_ecs::types::LoadBalancer::builder()
+                    .target_group_arn(
+                        "arn:aws:elasticloadbalancing:us-east-1:000000000000:targetgroup/web/abc",
+                    )
+                    .container_name("app")
+                    .container_port(80)
+                    .build(),
+            )
+            .health_check_grace_period_seconds(60)
+            .build();
+        assert_eq!(
+            service.content(),
+            r#"Service "web" status=ACTIVE desired=3 running=2 pending=1 load-balancers=1 health-check-grace=60s"#
+        );
+        assert_eq!(
+            Service::builder().build().content(),
+            r#"Service "unknown" status=unknown desired=0 running=0 pending=0 load-balancers=0"#
+        );
+    }
+
+    #[test]
+    fn test_task_content() {
+        let task = Task::builder()
+            .task_arn("arn:aws:ecs:us-east-1:000000000000:task/prod/abc123")
+            .last_status("RUNNING")
+            .desired_status("RUNNING")
+            .launch_type(LaunchType::Fargate)
+            .task_definition_arn("arn:aws:ecs:us-east-1:000000000000:task-definition/my-app:42")
+            .build();
+        assert_eq!(
+            task.content(),
+            "Task abc123 status=RUNNING desired=RUNNING launch-type=FARGATE taskdef=my-app:42 exec=disabled age=pending"
+        );
+        assert_eq!(
+            Task::builder().build().content(),
+            "Task unknown status=unknown desired=unknown launch-type=unknown exec=disabled age=pending"
+        );
+        let exec = Task::builder().enable_execute_command(true).build();
+        assert!(exec.content().contains(" exec=enabled "));
+    }
+
+    #[test]
+    fn test_task_age() {
+        let now = UNIX_EPOCH + Duration::from_secs(10_000);
+        let at = DateTime::from_secs;
+        let running = Task::builder().started_at(at(10_000 - 7_980)).build();
+        assert_eq!(task_age(&running, now), " age=2h13m");
+
+        let stopped = Task::builder()
+            .started_at(at(1_000))
+            .stopped_at(at(1_045))
+            .build();
+        assert_eq!(task_age(&stopped, now), " lifetime=45s");
+
+        let pending = Task::builder().created_at(at(10_000 - 200)).build();
+        assert_eq!(task_age(&pending, now), " age=pending pending-for=3m20s");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1_800)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5_400)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("0s").is_err());
+    }
+
+    #[test]
+    fn test_is_recent() {
+        let now = UNIX_EPOCH + Duration::from_secs(10_000);
+        let stopped_at = |secs| {
+            Task::builder()
+                .stopped_at(DateTime::from_secs(secs))
+                .build()
+        };
+        let window = Duration::from_secs(3_600);
+        assert!(is_recent(&stopped_at(9_000), window, now));
+        assert!(!is_recent(&stopped_at(6_000), window, now));
+        assert!(is_recent(&Task::builder().build(), window, now));
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let service = |deployment: Deployment| {
+            Service::builder()
+                .deployment_configuration(
+                    DeploymentConfiguration::builder()
+                        .deployment_circuit_breaker(
+                            DeploymentCircuitBreaker::builder()
+                                .enable(true)
+                                .rollback(true)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .deployments(deployment)
+                .build()
+        };
+
+        let healthy = service(
+            Deployment::builder()
+                .id("ecs-svc/1")
+                .rollout_state(DeploymentRolloutState::Completed)
+                .build(),
+        );
+        assert_eq!(
+            CircuitBreaker::from_service(&healthy).unwrap().content(),
+            "Circuit breaker enabled rollback=true not-tripped"
+        );
+
+        let tripped = service(
+            Deployment::builder()
+                .id("ecs-svc/2")
+                .rollout_state(DeploymentRolloutState::Failed)
+                .failed_tasks(3)
+                .rollout_state_reason("ECS deployment circuit breaker: tasks failed to start.")
+                .build(),
+        );
+        assert_eq!(
+            CircuitBreaker::from_service(&tripped).unwrap().content(),
+            "Circuit breaker enabled rollback=true tripped deployment=ecs-svc/2 failed-tasks=3 reason=\"ECS deployment circuit breaker: tasks failed to start.\" <== rolled back"
+        );
+
+        assert!(CircuitBreaker::from_service(&Service::builder().build()).is_none());
+    }
+
+    #[test]
+    fn test_service_connect_ports() {
+        let service = Service::builder()
+            .deployments(
+                Deployment::builder()
+                    .status("PRIMARY")
+                    .service_connect_configuration(
+                        ServiceConnectConfiguration::builder()
+                            .enabled(true)
+                            .namespace("internal")
+                            .services(
+                                ServiceConnectService::builder()
+                                    .port_name("http")
+                                    .discovery_name("web")
+                                    .client_aliases(
+                                        ServiceConnectClientAlias::builder()
+                                            .port(80)
+                                            .dns_name("web.internal")
+                                            .build()
+                                            .unwrap(),
+                                    )
+                                    .build()
+                                    .unwrap(),
+                            )
+                            .build()
+                            .unwrap(),
+                    )
+                    .build(),
+            )
+            .build();
+        let ports = ServiceConnectPort::from_service(&service);
+        assert_eq!(ports.len(), 1);
+        assert_eq!(
+            ports[0].content(),
+            "Service Connect namespace=internal port-name=http discovery-name=web aliases=[web.internal:80]"
+        );
+        assert!(ServiceConnectPort::from_service(&Service::builder().build()).is_empty());
+    }
+
+    #[test]
+    fn test_cloud_map_registry_content() {
+        let registry = CloudMapRegistry {
+            registry_arn: "arn:aws:servicediscovery:us-east-1:000000000000:service/srv-1"
+                .to_string(),
+            dns_name: Some("web.internal".to_string()),
+            container: Some("app".to_string()),
+            port: Some(8080),
+        };
+        assert_eq!(
+            registry.content(),
+            "Cloud Map dns=web.internal container=app port=8080"
+        );
+    }
+
+    #[test]
+    fn test_cap_page() {
+        let arns: Vec<String> = ["a", "b", "c"].map(String::from).to_vec();
+        assert_eq!(cap_page(&arns, 0, None), (&arns[..], None));
+        assert_eq!(cap_page(&arns, 0, Some(5)), (&arns[..], None));
+        assert_eq!(cap_page(&arns, 3, Some(5)), (&arns[..2], Some(1)));
+        assert_eq!(cap_page(&arns, 5, Some(5)), (&arns[..0], Some(3)));
+    }
+
+    #[test]
+    fn test_container_instance_content() {
+        let resource =
+            |name: &str, value| Resource::builder().name(name).integer_value(value).build();
+        let instance = ContainerInstance::builder()
+            .ec2_instance_id("i-0123456789abcdef0")
+            .status("ACTIVE")
+            .running_tasks_count(2)
+            .registered_resources(resource("CPU", 2048))
+            .registered_resources(resource("MEMORY", 3904))
+            .remaining_resources(resource("CPU", 1024))
+            .remaining_resources(resource("MEMORY", 1856))
+            .build();
+        assert_eq!(
+            instance.content(),
+            "Container instance ec2=i-0123456789abcdef0 status=ACTIVE running-tasks=2 cpu=1024/2048 memory=1856/3904"
+        );
+        assert_eq!(
+            ContainerInstance::builder().build().content(),
+            "Container instance ec2=unknown status=unknown running-tasks=0 cpu=unknown/unknown memory=unknown/unknown"
+        );
+    }
+
+    #[test]
+    fn test_task_network() {
+        let task = Task::builder()
+            .last_status("RUNNING")
+            .attachments(
+                Attachment::builder()
+                    .r#type("ElasticNetworkInterface")
+                    .details(
+                        KeyValuePair::builder()
+                            .name("networkInterfaceId")
+                            .value("eni-123")
+                            .build(),
+                    )
+                    .details(
+                        KeyValuePair::builder()
+                            .name("privateIPv4Address")
+                            .value("10.0.1.5")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            TaskNetwork::from_task(&task).unwrap().content(),
+            "Network private-ip=10.0.1.5 eni=eni-123"
+        );
+
+        let ec2_task = Task::builder()
+            .last_status("RUNNING")
+            .container_instance_arn(
+                "arn:aws:ecs:us-east-1:000000000000:container-instance/prod/ci-1",
+            )
+            .build();
+        assert_eq!(
+            TaskNetwork::from_task(&ec2_task).unwrap().content(),
+            "Network container-instance=ci-1"
+        );
+
+        let provisioning = Task::builder().last_status("PROVISIONING").build();
+        assert_eq!(
+            TaskNetwork::from_task(&provisioning).unwrap().content(),
+            "Network pending"
+        );
+
+        let running = Task::builder().last_status("RUNNING").build();
+        assert!(TaskNetwork::from_task(&running).is_none());
+    }
+
+    #[test]
+    fn test_container_content() {
+        let container = ContainerInfo {
+            name: "app".to_string(),
+            image: "nginx:latest".to_string(),
+            command: Some(vec!["nginx".to_string(), "-g".to_string()]),
+            last_status: Some("RUNNING".to_string()),
+            depends_on: Vec::new(),
+            image_digest: None,
+            log_config: None,
+            health_check: None,
+            health_status: None,
+            cpu: None,
+            memory: None,
+            memory_reservation: None,
+            exec_agent_status: None,
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" repo=nginx tag=latest status=RUNNING command=["nginx", "-g"]"#
+        );
+
+        let sized = ContainerInfo {
+            cpu: Some(128),
+            memory: Some(256),
+            memory_reservation: Some(128),
+            ..container.clone()
+        };
+        assert_eq!(
+            sized.content(),
+            r#"Container "app" repo=nginx tag=latest status=RUNNING cpu=128 mem=256(res 128) command=["nginx", "-g"]"#
+        );
+        let reserved_only = ContainerInfo {
+            cpu: None,
+            memory: None,
+            ..sized
+        };
+        assert_eq!(
+            reserved_only.content(),
+            r#"Container "app" repo=nginx tag=latest status=RUNNING mem-reservation=128 command=["nginx", "-g"]"#
+        );
+
+        let container = ContainerInfo {
+            command: None,
+            last_status: None,
+            ..container
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" repo=nginx tag=latest status=unknown"#
+        );
+
+        let container = ContainerInfo {
+            depends_on: vec![
+                ("db".to_string(), "HEALTHY".to_string()),
+                ("init".to_string(), "COMPLETE".to_string()),
+            ],
+            ..container
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" repo=nginx tag=latest status=unknown waits-for=db(HEALTHY),init(COMPLETE)"#
+        );
+
+        let container = ContainerInfo {
+            image: "123456789012.dkr.ecr.us-east-1.amazonaws.com/app:v1".to_string(),
+            image_digest: Some("sha256:abc".to_string()),
+            depends_on: Vec::new(),
+            ..container
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" registry=123456789012.dkr.ecr.us-east-1.amazonaws.com repo=app tag=v1 running-digest=sha256:abc status=unknown"#
+        );
+
+        let container = ContainerInfo {
+            image: "nginx:latest".to_string(),
+            image_digest: None,
+            log_config: Some(LogConfig {
+                driver: "awslogs".to_string(),
+                group: Some("/ecs/app".to_string()),
+                stream_prefix: Some("web".to_string()),
+            }),
+            ..container
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" repo=nginx tag=latest status=unknown logs=awslogs log-group=/ecs/app log-stream-prefix=web"#
+        );
+
+        let container = ContainerInfo {
+            log_config: None,
+            health_check: Some(HealthCheckInfo {
+                command: vec!["CMD-SHELL".to_string(), "exit 0".to_string()],
+                interval: Some(10),
+                timeout: Some(5),
+                retries: Some(3),
+            }),
+            health_status: Some("HEALTHY".to_string()),
+            ..container
+        };
+        assert_eq!(
+            container.content(),
+            r#"Container "app" repo=nginx tag=latest status=unknown health=HEALTHY health-check=["CMD-SHELL", "exit 0"] interval=10s timeout=5s retries=3"#
+        );
+
+        let exec = ContainerInfo {
+            health_check: None,
+            exec_agent_status: Some("RUNNING".to_string()),
+            ..container
+        };
+        assert_eq!(
+            exec.content(),
+            r#"Container "app" repo=nginx tag=latest status=unknown exec-agent=RUNNING"#
+        );
+    }
+
+    #[test]
+    fn test_parse_image_ref() {
+        assert_eq!(
+            ImageRef::parse("bitnami/redis"),
+            ImageRef {
+                registry: None,
+                repository: "bitnami/redis",
+                tag: None,
+                digest: None,
+            }
+        );
+        assert_eq!(
+            ImageRef::parse("localhost:5000/team/app:1.2@sha256:abc"),
+            ImageRef {
+                registry: Some("localhost:5000"),
+                repository: "team/app",
+                tag: Some("1.2"),
+                digest: Some("sha256:abc"),
+            }
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use aws_sdk_elasticloadbalancingv2::error::ErrorMetadata;
+use color_eyre::eyre;
+use std::fmt;
+
+/// Returned when an interactive selection finishes without a resource, e.g. because nothing
+/// matched the query
+///
+/// The value is the kind of resource that was being selected, e.g. "load balancer".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NothingSelected(pub &'static str);
+
+impl fmt::Display for NothingSelected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No {} selected", self.0)
+    }
+}
+
+impl std::error::Error for NothingSelected {}
+
+/// Returned when the user aborts an interactive selection with Esc or Ctrl-C
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Selection cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Returned when a resource no longer exists, e.g. because it was deleted after being listed in
+/// an interactive selection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotFound {
+    /// The kind of resource, e.g. "load balancer"
+    pub kind: &'static str,
+    /// The ARN or id of the resource
+    pub id: String,
+}
+
+impl NotFound {
+    pub fn new(kind: &'static str, id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+        }
+    }
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The {} {} no longer exists", self.kind, self.id)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Error returned by the library functions, so callers can tell the kinds of failure apart without
+/// downcasting
+///
+/// The variants that wrap an [`eyre::Report`] keep the context of what was being done when the
+/// call failed.
+#[derive(Debug, thiserror::Error)]
+pub enum LbtreeError {
+    #[error(transparent)]
+    NotFound(#[from] NotFound),
+    /// AWS rejected a call for exceeding the request rate, even after the SDK retried it
+    #[error("{0:#}")]
+    Throttled(eyre::Report),
+    /// The credentials are not allowed to make one of the calls
+    #[error("{0:#}")]
+    AccessDenied(eyre::Report),
+    #[error(transparent)]
+    NoSelection(#[from] NothingSelected),
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+    /// Any other failure, usually of an AWS call
+    #[error("{0:#}")]
+    Aws(eyre::Report),
+}
+
+impl LbtreeError {
+    /// Turn the error back into a report, keeping the chain of causes for color-eyre to render
+    pub fn into_report(self) -> eyre::Report {
+        match self {
+            LbtreeError::Throttled(report)
+            | LbtreeError::AccessDenied(report)
+            | LbtreeError::Aws(report) => report,
+            LbtreeError::NotFound(e) => e.into(),
+            LbtreeError::NoSelection(e) => e.into(),
+            LbtreeError::Cancelled(e) => e.into(),
+        }
+    }
+}
+
+impl From<eyre::Report> for LbtreeError {
+    /// Classify a report by the typed error it wraps, or the code of the AWS error that caused it
+    fn from(report: eyre::Report) -> Self {
+        let report = match report.downcast::<LbtreeError>() {
+            Ok(e) => return e,
+            Err(report) => report,
+        };
+        if let Some(e) = report.downcast_ref::<NotFound>() {
+            return e.clone().into();
+        }
+        if let Some(e) = report.downcast_ref::<NothingSelected>() {
+            return e.clone().into();
+        }
+        if report.is::<Cancelled>() {
+            return Cancelled.into();
+        }
+        let code = error_code(&report);
+        if is_throttling(code) {
+            LbtreeError::Throttled(report)
+        } else if is_access_denied(code) {
+            LbtreeError::AccessDenied(report)
+        } else {
+            LbtreeError::Aws(report)
+        }
+    }
+}
+
+/// Code of the AWS error that caused a report, if any
+fn error_code(report: &eyre::Report) -> Option<&str> {
+    // every service reports the code of an error it did not model in the same metadata type
+    report
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<ErrorMetadata>())
+        .and_then(|meta| meta.code())
+}
+
+/// Whether an AWS error code means the request was rate limited
+pub fn is_throttling(code: Option<&str>) -> bool {
+    matches!(
+        code,
+        Some(
+            "Throttling"
+                | "ThrottlingException"
+                | "RequestLimitExceeded"
+                | "TooManyRequestsException"
+        )
+    )
+}
+
+/// Whether an AWS error code means the credentials lack a permission
+pub fn is_access_denied(code: Option<&str>) -> bool {
+    matches!(
+        code,
+        Some(
+            "AccessDenied"
+                | "AccessDeniedException"
+                | "UnauthorizedOperation"
+                | "UnauthorizedException"
+        )
+    )
+}
+
+/// Whether a report was caused by the credentials lacking a permission, e.g. to leave out one
+/// branch of a tree under a least-privilege role rather than failing the whole tree
+pub fn is_access_denied_report(report: &eyre::Report) -> bool {
+    is_access_denied(error_code(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_throttling() {
+        assert!(is_throttling(Some("Throttling")));
+        assert!(is_throttling(Some("TooManyRequestsException")));
+        assert!(!is_throttling(Some("TargetGroupNotFound")));
+        assert!(!is_throttling(None));
+    }
+
+    #[test]
+    fn test_classify_report() {
+        let not_found: eyre::Report = NotFound::new("load balancer", "arn:lb").into();
+        assert!(matches!(
+            LbtreeError::from(not_found.wrap_err("describing load balancer")),
+            LbtreeError::NotFound(_)
+        ));
+
+        let denied = ErrorMetadata::builder()
+            .code("AccessDenied")
+            .message("not authorized")
+            .build();
+        let denied = eyre::Report::new(denied).wrap_err("describing load balancers");
+        match LbtreeError::from(denied) {
+            LbtreeError::AccessDenied(report) => {
+                assert!(report.to_string().contains("describing load balancers"))
+            }
+            other => panic!("expected AccessDenied, got {other:?}"),
+        }
+
+        let denied = eyre::Report::new(
+            ErrorMetadata::builder()
+                .code("AccessDenied")
+                .message("not authorized")
+                .build(),
+        )
+        .wrap_err("describing rules for listener");
+        assert!(is_access_denied_report(&denied));
+        assert!(!is_access_denied_report(&eyre::eyre!("parsing snapshot")));
+
+        let other = LbtreeError::from(eyre::eyre!("parsing snapshot"));
+        assert!(matches!(other, LbtreeError::Aws(_)));
+        // wrapping the error in a report and classifying it again gives back the same variant
+        assert!(matches!(
+            LbtreeError::from(eyre::Report::from(LbtreeError::from(NothingSelected(
+                "api"
+            )))),
+            LbtreeError::NoSelection(NothingSelected("api"))
+        ));
+    }
+}
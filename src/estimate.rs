@@ -0,0 +1,61 @@
+use std::fmt;
+
+/// Resource counts and the number of AWS calls a full render would make, for `--count-only`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    /// (kind of resource, number found), in the order they were first counted
+    counts: Vec<(&'static str, usize)>,
+    /// describe calls a full render of everything counted would make
+    pub calls: usize,
+}
+
+impl Estimate {
+    /// Add `n` resources of the given kind
+    pub fn count(&mut self, kind: &'static str, n: usize) {
+        match self.counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, total)) => *total += n,
+            None => self.counts.push((kind, n)),
+        }
+    }
+
+    /// Combine the estimate for another resource type into this one
+    pub fn merge(&mut self, other: Estimate) {
+        for (kind, n) in other.counts {
+            self.count(kind, n);
+        }
+        self.calls += other.calls;
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, n) in &self.counts {
+            writeln!(f, "{kind}: {n}")?;
+        }
+        write!(f, "Estimated AWS calls for a full render: ~{}", self.calls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_merges_counts_by_kind() {
+        let mut estimate = Estimate::default();
+        estimate.count("load balancers", 2);
+        estimate.count("listeners", 3);
+        estimate.calls = 10;
+
+        let mut other = Estimate::default();
+        other.count("listeners", 1);
+        other.count("clusters", 1);
+        other.calls = 4;
+        estimate.merge(other);
+
+        assert_eq!(
+            estimate.to_string(),
+            "load balancers: 2\nlisteners: 4\nclusters: 1\nEstimated AWS calls for a full render: ~14"
+        );
+    }
+}
@@ -1,6 +1,18 @@
 // Library interface for lbtree, exposing modules for testing
 
+mod age;
 pub mod alb;
 pub mod apigateway;
+pub mod diff;
 pub mod ecs;
+pub mod error;
+pub mod estimate;
+pub mod limits;
+pub mod model;
+pub mod pager;
+mod pagination;
+mod picker;
 pub mod present;
+mod progress;
+mod resolve;
+pub mod tree;
@@ -0,0 +1,174 @@
+use aws_config::SdkConfig;
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancerTypeEnum;
+use color_eyre::eyre::Context;
+use std::collections::HashMap;
+use tracing::{Instrument, info_span};
+
+use crate::error::LbtreeError;
+use crate::pagination::collect_all;
+use crate::present::{OutputWriter, Present};
+use crate::progress::Spinner;
+use crate::tree::Node;
+
+/// Percentage of a limit in use from which it is flagged as close to its maximum
+const NEAR_LIMIT_PERCENT: u64 = 80;
+
+/// An ELB account limit, with how much of it is used where that can be counted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountLimit {
+    pub name: String,
+    pub max: Option<String>,
+    /// Only known for the account-wide limits on load balancers and target groups, not for
+    /// per-load-balancer limits such as `listeners-per-application-load-balancer`
+    pub used: Option<usize>,
+}
+
+impl AccountLimit {
+    fn is_near_max(&self) -> bool {
+        let (Some(used), Some(max)) = (self.used, self.max.as_deref()) else {
+            return false;
+        };
+        match max.parse::<u64>() {
+            Ok(max) => used as u64 * 100 >= max * NEAR_LIMIT_PERCENT,
+            Err(_) => false,
+        }
+    }
+}
+
+impl Present for AccountLimit {
+    fn content(&self) -> String {
+        let mut content = format!(
+            "Limit {} max={}",
+            self.name,
+            self.max.as_deref().unwrap_or("unknown")
+        );
+        if let Some(used) = self.used {
+            content.push_str(&format!(" used={used}"));
+        }
+        if self.is_near_max() {
+            content.push_str(" <== near limit");
+        }
+        content
+    }
+}
+
+/// Fetch the ELB limits of the account, with the number of load balancers and target groups
+/// counted against them
+pub async fn fetch_account_limits(config: &SdkConfig) -> Result<Vec<AccountLimit>, LbtreeError> {
+    let client = aws_sdk_elasticloadbalancingv2::Client::new(config);
+    let spinner = Spinner::new("Fetching account limits");
+
+    let mut limits = Vec::new();
+    let mut marker = None;
+    loop {
+        let response = client
+            .describe_account_limits()
+            .set_marker(marker)
+            .send()
+            .instrument(info_span!("describe_account_limits"))
+            .await
+            .context("describing account limits")?;
+        limits.extend_from_slice(response.limits());
+        marker = response.next_marker().map(|m| m.to_string());
+        if marker.is_none() {
+            break;
+        }
+    }
+
+    let load_balancers = collect_all(
+        client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing load balancers",
+    )
+    .instrument(info_span!("describe_load_balancers"))
+    .await?;
+    let target_groups = collect_all(
+        client
+            .describe_target_groups()
+            .into_paginator()
+            .items()
+            .send(),
+        "describing target groups",
+    )
+    .instrument(info_span!("describe_target_groups"))
+    .await?;
+    drop(spinner);
+
+    let mut used: HashMap<&str, usize> = HashMap::new();
+    for lb in &load_balancers {
+        let limit = match lb.r#type() {
+            Some(LoadBalancerTypeEnum::Application) => "application-load-balancers",
+            Some(LoadBalancerTypeEnum::Network) => "network-load-balancers",
+            Some(LoadBalancerTypeEnum::Gateway) => "gateway-load-balancers",
+            _ => continue,
+        };
+        *used.entry(limit).or_default() += 1;
+    }
+    used.insert("target-groups", target_groups.len());
+
+    Ok(limits
+        .iter()
+        .map(|limit| {
+            let name = limit.name().unwrap_or("unknown");
+            AccountLimit {
+                name: name.to_string(),
+                max: limit.max().map(|m| m.to_string()),
+                // limits without any resources yet are at zero rather than unknown
+                used: match name {
+                    "application-load-balancers"
+                    | "network-load-balancers"
+                    | "gateway-load-balancers"
+                    | "target-groups" => Some(used.get(name).copied().unwrap_or_default()),
+                    _ => None,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Display the ELB limits of the account
+pub async fn display_account_limits(
+    config: &SdkConfig,
+    writer: &dyn OutputWriter,
+) -> Result<(), LbtreeError> {
+    let limits = fetch_account_limits(config).await?;
+    let mut root = Node::new("Account limits");
+    root.children = limits
+        .iter()
+        .map(|limit| Node::new(limit.content()))
+        .collect();
+    root.render(writer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max: &str, used: Option<usize>) -> AccountLimit {
+        AccountLimit {
+            name: "application-load-balancers".to_string(),
+            max: Some(max.to_string()),
+            used,
+        }
+    }
+
+    #[test]
+    fn test_limit_content() {
+        assert_eq!(
+            limit("50", Some(3)).content(),
+            "Limit application-load-balancers max=50 used=3"
+        );
+        assert_eq!(
+            limit("50", Some(40)).content(),
+            "Limit application-load-balancers max=50 used=40 <== near limit"
+        );
+        assert_eq!(
+            limit("50", None).content(),
+            "Limit application-load-balancers max=50"
+        );
+    }
+}
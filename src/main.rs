@@ -1,16 +1,53 @@
+mod age;
 mod alb;
 mod apigateway;
+mod arn;
+mod completions;
+mod config;
+mod diff;
 mod ecs;
+mod error;
+mod estimate;
+mod limits;
+mod mfa;
+mod model;
+mod pager;
+mod pagination;
+mod picker;
 mod present;
+mod progress;
+mod resolve;
+mod tree;
 
-use clap::{Parser, Subcommand};
-use color_eyre::eyre;
+use aws_config::Region;
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::timeout::TimeoutConfig;
+use clap::{Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{self, Context};
 use crossbeam::channel::unbounded;
+use serde::Deserialize;
 use skim::prelude::*;
 use std::borrow::Cow;
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
 
-use present::StdoutWriter;
+use arn::ArnTarget;
+use error::{Cancelled, LbtreeError, NothingSelected};
+use estimate::Estimate;
+use pager::Paging;
+use present::{
+    ArnWriter, CommandWriter, FileWriter, HtmlWriter, IndentWriter, JsonLinesWriter, JsonWriter,
+    MarkdownWriter, MaxDepthWriter, MaxItemsWriter, MermaidWriter, OutputWriter, PlainWriter,
+    PrefixWriter, ShowArnsWriter, StdoutWriter, TeeWriter,
+};
+use tree::Node;
+
+/// Value of `--region` that queries every region enabled in the account
+const ALL_REGIONS: &str = "all";
 
 #[derive(Parser)]
 #[command(name = "lbtree")]
@@ -18,35 +55,339 @@ use present::StdoutWriter;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Output format [default: tree]
+    ///
+    /// This and the other defaults can be changed in `~/.config/lbtree/config.toml`.
+    #[arg(short, long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Log AWS calls and their timings to stderr (-v for info, -vv for debug including AWS SDK
+    /// requests)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings, e.g. about methods without an integration, to stderr; errors are
+    /// still shown. With --output-file, the output is also only written to the file
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Only print the ARN (or id) of each resource, one per line; shorthand for `--output arns`
+    #[arg(long, global = true)]
+    arns_only: bool,
+
+    /// Print the AWS CLI commands that describe each resource; shorthand for `--output commands`
+    #[arg(long, global = true, conflicts_with = "arns_only")]
+    emit_commands: bool,
+
+    /// Only show resources down to this depth, where the root of each tree is at depth 0
+    #[arg(long, global = true, value_name = "N")]
+    depth: Option<usize>,
+
+    /// Show at most N resources under each parent, and list at most N load balancers, clusters or
+    /// services to choose from, replacing the rest with `... (truncated, N more)`
+    #[arg(long, global = true, value_name = "N")]
+    max_items: Option<usize>,
+
+    /// Show how long ago load balancers and ECS services were created, where the API returns it
+    #[arg(long, global = true)]
+    age: bool,
+
+    /// Number of spaces each level of the tree is indented by, for `--output tree` and `--output
+    /// plain` [default: 2]
+    #[arg(long, global = true, value_name = "WIDTH")]
+    indent: Option<usize>,
+
+    /// Whether to colour progress spinners and error reports [default: auto]
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<config::ColorChoice>,
+
+    /// Text written before each resource with `--output plain`, e.g. "* " [default: none]
+    #[arg(long, global = true, value_name = "MARKER")]
+    leaf_marker: Option<String>,
+
+    /// Append the full ARN (or id) of each resource to its line, for `--output tree`, `plain` and
+    /// `markdown`
+    #[arg(long, global = true)]
+    show_arns: bool,
+
+    /// Start each line with `[LABEL] `, e.g. to tell apart the output of several accounts
+    /// collected in one log, for `--output tree`, `plain`, `arns` and `commands` [default when
+    /// given without a value: the profile and region, e.g. prod/eu-west-1]
+    #[arg(long, global = true, value_name = "LABEL", num_args = 0..=1)]
+    context: Option<Option<String>>,
+
+    /// Indent JSON output (`--output json` and `--output model`) instead of writing it on one
+    /// line
+    #[arg(long, global = true)]
+    json_pretty: bool,
+
+    /// Print only the values of one field of a load balancer's model, one per line, e.g.
+    /// `target.state`, `listener.port` or `load_balancer.dns_name`
+    ///
+    /// The path is dotted field names from `--output model`, stepping into lists, and may start
+    /// with `listener`, `rule`, `action`, `target_group` or `target` to find those anywhere.
+    #[arg(long, global = true, value_name = "PATH")]
+    select: Option<String>,
+
+    /// Always send output through `$PAGER` (or `less -R`) when stdout is a terminal, rather than
+    /// only when it does not fit on screen
+    #[arg(long, global = true)]
+    pager: bool,
+
+    /// Never send output through a pager
+    #[arg(long, global = true, conflicts_with = "pager")]
+    no_pager: bool,
+
+    /// AWS region to query instead of the one from the environment or profile, or `all` for every
+    /// region enabled in the account with `elbv2 --all`
+    ///
+    /// `all` also needs the `ec2:DescribeRegions` permission, and takes roughly as long as the
+    /// slowest few regions since up to four are fetched at once.
+    #[arg(long, global = true, value_name = "REGION")]
+    region: Option<String>,
+
+    /// Profile from the shared AWS config to use instead of `$AWS_PROFILE`
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Run `elbv2 --all` with every profile in the shared AWS config, with each profile as its
+    /// own tree
+    #[arg(long, global = true, conflicts_with = "profile")]
+    all_profiles: bool,
+
+    /// ARN of an IAM role to assume, e.g. to inspect resources in another account
+    #[arg(long, global = true, value_name = "ARN")]
+    assume_role_arn: Option<String>,
+
+    /// External id required by the trust policy of the role given with --assume-role-arn
+    #[arg(long, global = true, requires = "assume_role_arn")]
+    external_id: Option<String>,
+
+    /// Name of the session when assuming --assume-role-arn, shown in CloudTrail
+    #[arg(
+        long,
+        global = true,
+        default_value = "lbtree",
+        requires = "assume_role_arn"
+    )]
+    session_name: String,
+
+    /// Give up on an AWS call that has not completed after this many seconds, including retries
+    #[arg(long, global = true, value_name = "SECONDS", default_value_t = 30)]
+    timeout: u64,
+
+    /// Only count the top-level resources and estimate how many AWS calls a full render would
+    /// make, printed to stderr
+    #[arg(long, global = true)]
+    count_only: bool,
+
+    /// Read the ARN or id of the resource to display from stdin instead of opening the picker,
+    /// e.g. `lbtree elbv2 --all --arns-only | grep prod-lb | lbtree elbv2 --stdin`
+    ///
+    /// This is the default when stdin is not a terminal and the picker would otherwise be opened,
+    /// i.e. not with `--count-only`, `--from-file`, `--output model` or `--select`.
+    #[arg(long, global = true)]
+    stdin: bool,
+
+    /// Render a tree previously saved with `--output json` instead of querying AWS
+    #[arg(long, value_name = "PATH")]
+    from_file: Option<PathBuf>,
+
+    /// Also write the output to this file, in the same format as on screen, e.g. to keep a copy
+    /// of an interactive session
+    #[arg(long, global = true, value_name = "PATH")]
+    output_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    /// Indented tree
+    Tree,
+    /// Indented tree without the `-> ` prefix, for embedding in other tools
+    Plain,
+    /// Mermaid flowchart, for pasting into a ```mermaid block
+    Mermaid,
+    /// Nested JSON, which can be rendered again with --from-file
+    Json,
+    /// Nested Markdown bullet list
+    Markdown,
+    /// Self-contained HTML page with collapsible subtrees
+    Html,
+    /// One JSON object per resource, flushed line by line as each tree is rendered
+    #[value(name = "jsonl")]
+    #[serde(rename = "jsonl")]
+    JsonLines,
+    /// Only the ARN (or id) of each resource, one per line
+    Arns,
+    /// AWS CLI commands that describe each resource
+    Commands,
+    /// Typed JSON document with a field per value, versioned by `schema_version`; only for a
+    /// single load balancer
+    Model,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Display Application Load Balancer tree
     Elbv2 {
-        /// ARN of the load balancer (interactive selection if not provided)
+        /// ARN, partial ARN or name of the load balancer (interactive selection if not provided)
         #[arg(short, long)]
         load_balancer_arn: Option<String>,
+
+        /// Display every load balancer whose name matches this glob, e.g. `prod-*`, instead of
+        /// selecting one
+        #[arg(long, value_name = "GLOB", conflicts_with = "load_balancer_arn")]
+        name: Option<String>,
+
+        /// Display every load balancer in the region, grouped by type, instead of selecting one
+        #[arg(long, conflicts_with_all = ["load_balancer_arn", "name"])]
+        all: bool,
+
+        /// URL of a request to route through the listener rules, highlighting the rule that
+        /// would serve it
+        #[arg(long, value_name = "URL")]
+        test_request: Option<String>,
+
+        /// HTTP method used with --test-request
+        #[arg(long, default_value = "GET")]
+        method: String,
+
+        /// Show load balancer and target group attributes such as access logs, deregistration
+        /// delay and stickiness, and the health check of each target group with the codes it
+        /// expects
+        #[arg(long)]
+        attributes: bool,
+
+        /// Show the WAF web ACL associated with the load balancer
+        #[arg(long)]
+        waf: bool,
+
+        /// Show the tags of listener rules, such as the team that owns a rule or the ticket that
+        /// changed it
+        #[arg(long)]
+        tags: bool,
+
+        /// Order target groups and targets instead of keeping the order AWS returns; listeners
+        /// are always ordered by port
+        #[arg(long, value_enum, value_name = "KEY")]
+        sort: Option<alb::SortKey>,
+
+        /// Print one line per load balancer instead of its tree: name, scheme, type, number of
+        /// listeners and number of targets with the healthy ones in brackets
+        #[arg(long)]
+        oneline: bool,
+
+        /// Number of times to fetch a target group's health again when throttled before showing
+        /// it as unavailable, on top of the AWS SDK's own retries
+        #[arg(long, value_name = "N", default_value_t = alb::DEFAULT_HEALTH_RETRIES)]
+        health_retries: u32,
+
+        /// Only show target groups with a target that is not healthy, those targets, and the
+        /// listener rules that forward to them
+        #[arg(long)]
+        only_unhealthy: bool,
+
+        /// Show `<no rules>` under listeners without rules and `<no targets>` under target groups
+        /// without targets, rather than leaving them without children
+        #[arg(long)]
+        show_empty: bool,
+    },
+
+    /// Display a single target group, the load balancers that use it and the health of its
+    /// targets
+    TargetGroup {
+        /// ARN of the target group
+        arn: String,
+
+        /// Show target group attributes such as deregistration delay and stickiness, and its
+        /// health check with the codes it expects
+        #[arg(long)]
+        attributes: bool,
+
+        /// Order targets instead of keeping the order AWS returns
+        #[arg(long, value_enum, value_name = "KEY")]
+        sort: Option<alb::SortKey>,
+
+        /// Number of times to fetch the health again when throttled before showing it as
+        /// unavailable, on top of the AWS SDK's own retries
+        #[arg(long, value_name = "N", default_value_t = alb::DEFAULT_HEALTH_RETRIES)]
+        health_retries: u32,
+
+        /// Only show the targets that are not healthy
+        #[arg(long)]
+        only_unhealthy: bool,
+
+        /// Show `<no targets>` when the target group has no targets, and `<no load balancers>`
+        /// when no load balancer uses it
+        #[arg(long)]
+        show_empty: bool,
     },
 
     /// Display API Gateway REST API tree
     #[clap(name = "apigateway")]
     ApiGateway {
-        /// ID of the REST API (interactive selection if not provided)
+        /// ID or name of the REST API (interactive selection if not provided)
         #[arg(short = 'i', long)]
         api_id: Option<String>,
     },
 
     /// Display ECS service hierarchy
     Ecs {
-        /// ARN of the cluster (interactive selection if not provided)
+        /// ARN or name of the cluster (interactive selection if not provided)
         #[arg(short, long)]
         cluster_arn: Option<String>,
 
-        /// ARN of the service (interactive selection if not provided)
+        /// ARN or name of the service (interactive selection if not provided)
         #[arg(short, long)]
         service_arn: Option<String>,
+
+        /// Show the digest of the image each container is running
+        #[arg(long)]
+        image_digests: bool,
+
+        /// List the EC2 container instances of the cluster with their remaining CPU and memory
+        #[arg(long)]
+        instances: bool,
+
+        /// Also show the tasks that stopped within this long, e.g. `30m` or `1h30m` [default
+        /// when given without a value: 1h]
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = ecs::parse_duration,
+            num_args = 0..=1,
+            default_missing_value = "1h"
+        )]
+        stopped_within: Option<Duration>,
+    },
+
+    /// Show the differences between two load balancers, e.g. for blue/green validation
+    ///
+    /// Each side is either the path to a tree saved with `--output json`, or the ARN, partial ARN
+    /// or name of a load balancer to fetch.
+    Diff {
+        /// Load balancer or saved tree shown as removed (`-`)
+        old: String,
+
+        /// Load balancer or saved tree shown as added (`+`)
+        new: String,
+    },
+
+    /// Show the ELB limits of the account, flagging those close to their maximum
+    Limits,
+
+    /// Display the tree of any supported resource from its ARN, e.g. a load balancer, listener,
+    /// target group, ECS cluster or service, or REST API
+    Resource {
+        /// Full ARN of the resource; it is looked up in the region the ARN names
+        arn: String,
     },
+
+    /// Print the completion script for a shell, e.g. `lbtree completions zsh >
+    /// ~/.zfunc/_lbtree`
+    Completions { shell: clap_complete::Shell },
 }
 
 #[derive(Debug, Clone)]
@@ -107,7 +448,7 @@ fn select_resource_type() -> eyre::Result<Option<ResourceType>> {
     let selected = match selected {
         Some(output) => {
             if output.is_abort {
-                return Ok(None);
+                return Err(Cancelled.into());
             }
 
             output.selected_items.first().and_then(|item| {
@@ -122,46 +463,667 @@ fn select_resource_type() -> eyre::Result<Option<ResourceType>> {
     Ok(selected)
 }
 
+/// Set up logging to stderr, keeping stdout clean for the tree output
+///
+/// Without `-v` only warnings are shown, as bare messages, and with `--quiet` nothing is.
+fn init_tracing(verbose: u8, quiet: bool) {
+    let filter = match verbose {
+        0 if quiet => return,
+        0 => {
+            tracing_subscriber::fmt()
+                .with_env_filter(EnvFilter::new("lbtree=warn"))
+                .without_time()
+                .with_target(false)
+                .with_writer(std::io::stderr)
+                .init();
+            return;
+        }
+        1 => "lbtree=info",
+        _ => "lbtree=debug,aws_smithy_runtime=debug,aws_smithy_runtime_api=debug,aws_config=debug",
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(filter))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     color_eyre::install()?;
 
-    let cli = Cli::parse();
-    let config = aws_config::load_from_env().await;
-    let writer = StdoutWriter;
+    let mut cli = Cli::parse();
+    init_tracing(cli.verbose, cli.quiet);
+    // needs neither AWS nor the config file
+    if let Some(Commands::Completions { shell }) = cli.command {
+        completions::write_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    let settings = config::Config::load(&cli)?;
+    // these never open a picker, so a closed or empty stdin in CI must not stop them
+    let picker_possible = !(cli.count_only
+        || cli.from_file.is_some()
+        || settings.output == OutputFormat::Model
+        || cli.select.is_some());
+    if cli.stdin || (picker_possible && !std::io::stdin().is_terminal()) {
+        select_from_stdin(&mut cli.command, std::io::stdin().lock())?;
+    }
+    settings.color.apply();
+    // JSON lines are read by other programs as they arrive, so they are never held for a pager
+    let paging = if settings.output == OutputFormat::JsonLines || cli.no_pager {
+        Paging::Never
+    } else if cli.pager {
+        Paging::Always
+    } else {
+        Paging::Auto
+    };
+    // the formatted lines go to the screen, the output file, or both
+    let sink: Box<dyn OutputWriter> = match &cli.output_file {
+        Some(path) => {
+            let file = FileWriter::create(path)
+                .wrap_err_with(|| format!("creating output file {}", path.display()))?;
+            if cli.quiet {
+                Box::new(file)
+            } else {
+                Box::new(TeeWriter::new(vec![
+                    Box::new(StdoutWriter::with_paging(paging)),
+                    Box::new(file),
+                ]))
+            }
+        }
+        None => Box::new(StdoutWriter::with_paging(paging)),
+    };
+    // a prefix would stop structured formats from parsing, and they have no lines to grep
+    let sink: Box<dyn OutputWriter> = match &cli.context {
+        Some(label)
+            if matches!(
+                settings.output,
+                OutputFormat::Tree
+                    | OutputFormat::Plain
+                    | OutputFormat::Arns
+                    | OutputFormat::Commands
+            ) =>
+        {
+            let label = label.clone().unwrap_or_else(|| settings.context_label());
+            Box::new(PrefixWriter::new(sink, &label))
+        }
+        _ => sink,
+    };
+    let writer: Box<dyn OutputWriter> = match settings.output {
+        OutputFormat::Tree => Box::new(IndentWriter::new(sink, settings.indent)),
+        OutputFormat::Plain => Box::new(
+            PlainWriter::new(sink, settings.indent)
+                .marker(cli.leaf_marker.clone().unwrap_or_default()),
+        ),
+        OutputFormat::Mermaid => Box::new(MermaidWriter::new(sink)),
+        OutputFormat::Json => Box::new(JsonWriter::new(sink).pretty(cli.json_pretty)),
+        OutputFormat::Markdown => Box::new(MarkdownWriter::new(sink)),
+        OutputFormat::Html => Box::new(HtmlWriter::new(sink)),
+        OutputFormat::JsonLines => Box::new(JsonLinesWriter::new(sink)),
+        OutputFormat::Arns => Box::new(ArnWriter::new(sink)),
+        OutputFormat::Commands => Box::new(CommandWriter::new(sink)),
+        // written directly by `run`, which serializes the model rather than rendered lines
+        OutputFormat::Model => sink,
+    };
+    // structured formats already carry the ARN in a field of their own
+    let writer: Box<dyn OutputWriter> = if cli.show_arns
+        && matches!(
+            settings.output,
+            OutputFormat::Tree | OutputFormat::Plain | OutputFormat::Markdown
+        ) {
+        Box::new(ShowArnsWriter::new(writer))
+    } else {
+        writer
+    };
+    let writer: Box<dyn OutputWriter> = match cli.depth {
+        Some(max_depth) => Box::new(MaxDepthWriter::new(writer, max_depth)),
+        None => writer,
+    };
+    let writer: Box<dyn OutputWriter> = match cli.max_items {
+        Some(max_items) => Box::new(MaxItemsWriter::new(writer, max_items)),
+        None => writer,
+    };
+    let writer = writer.as_ref();
+
+    if let Some(path) = &cli.from_file {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("reading snapshot {}", path.display()))?;
+        let roots: Vec<Node> = serde_json::from_str(&contents).wrap_err("parsing snapshot")?;
+        for root in &roots {
+            root.render(writer);
+        }
+        writer.flush();
+        return Ok(());
+    }
+
+    match run(cli, &settings, writer).await {
+        Ok(()) => {
+            writer.flush();
+            Ok(())
+        }
+        Err(e) => match LbtreeError::from(e) {
+            // closing the picker on purpose is not a failure, so scripts see a clean exit
+            LbtreeError::Cancelled(_) => Ok(()),
+            LbtreeError::NoSelection(nothing_selected) => {
+                eprintln!("{nothing_selected}");
+                std::process::exit(1);
+            }
+            // e.g. a resource deleted between listing it and describing it
+            LbtreeError::NotFound(not_found) => {
+                eprintln!("{not_found}");
+                std::process::exit(1);
+            }
+            e => Err(e.into_report()),
+        },
+    }
+}
+
+/// Fill in the resource the picker would otherwise be opened for with the ARN or id on stdin
+///
+/// For ECS a cluster is read if none was given, and otherwise a service.
+fn select_from_stdin(command: &mut Option<Commands>, input: impl BufRead) -> eyre::Result<()> {
+    let missing = match command {
+        Some(Commands::Elbv2 {
+            load_balancer_arn: arn @ None,
+            name: None,
+            all: false,
+            ..
+        }) => arn,
+        Some(Commands::ApiGateway { api_id: id @ None }) => id,
+        Some(Commands::Ecs {
+            cluster_arn: arn @ None,
+            ..
+        }) => arn,
+        Some(Commands::Ecs {
+            service_arn: arn @ None,
+            ..
+        }) => arn,
+        _ => return Ok(()),
+    };
+
+    let lines: Vec<String> = input
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err("reading stdin")?
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    match lines.as_slice() {
+        [id] => *missing = Some(id.clone()),
+        [] => eyre::bail!("expected an ARN or id on stdin, but it was empty"),
+        _ => eyre::bail!(
+            "expected a single ARN or id on stdin, got {} lines",
+            lines.len()
+        ),
+    }
+    Ok(())
+}
+
+/// Estimate the work for the resource given on the command line, or for every resource type if
+/// no subcommand was given
+async fn count_only(
+    config: &aws_config::SdkConfig,
+    command: Option<Commands>,
+) -> eyre::Result<Estimate> {
+    let estimate = match command {
+        Some(Commands::Elbv2 {
+            load_balancer_arn,
+            attributes,
+            waf,
+            tags,
+            ..
+        }) => alb::estimate_alb(config, load_balancer_arn, attributes, waf, tags).await?,
+        Some(Commands::ApiGateway { api_id }) => {
+            apigateway::estimate_apigateway(config, api_id).await?
+        }
+        Some(Commands::Ecs { cluster_arn, .. }) => ecs::estimate_ecs(config, cluster_arn).await?,
+        Some(Commands::Diff { old, new }) => {
+            let mut estimate = Estimate::default();
+            for side in [old, new] {
+                if !std::path::Path::new(&side).is_file() {
+                    estimate
+                        .merge(alb::estimate_alb(config, Some(side), false, false, false).await?);
+                }
+            }
+            estimate
+        }
+        Some(Commands::TargetGroup { attributes, .. }) => {
+            let mut estimate = Estimate::default();
+            estimate.count("target groups", 1);
+            // the target group, its health and the load balancers using it, and its attributes
+            // if asked for
+            estimate.calls = 3 + usize::from(attributes);
+            estimate
+        }
+        Some(Commands::Completions { .. }) => {
+            unreachable!("completions are written before AWS is configured")
+        }
+        Some(Commands::Limits) => Estimate {
+            // the limits, then the load balancers and target groups counted against them
+            calls: 3,
+            ..Estimate::default()
+        },
+        Some(Commands::Resource { arn }) => {
+            let parsed = arn::parse_arn(&arn)?;
+            let config = arn_config(config, parsed.region);
+            match parsed.target {
+                ArnTarget::LoadBalancer(arn) => {
+                    alb::estimate_alb(&config, Some(arn), false, false, false).await?
+                }
+                ArnTarget::TargetGroup(_) => {
+                    let mut estimate = Estimate::default();
+                    estimate.count("target groups", 1);
+                    estimate.calls = 3;
+                    estimate
+                }
+                ArnTarget::EcsCluster(cluster) | ArnTarget::EcsService { cluster, .. } => {
+                    ecs::estimate_ecs(&config, Some(cluster)).await?
+                }
+                ArnTarget::RestApi(id) => {
+                    apigateway::estimate_apigateway(&config, Some(id)).await?
+                }
+            }
+        }
+        None => {
+            let mut estimate = alb::estimate_alb(config, None, false, false, false).await?;
+            estimate.merge(apigateway::estimate_apigateway(config, None).await?);
+            estimate.merge(ecs::estimate_ecs(config, None).await?);
+            estimate
+        }
+    };
+    Ok(estimate)
+}
+
+/// Read one side of `lbtree diff`, from a saved tree if `side` is a file and otherwise by
+/// fetching the load balancer it names
+async fn load_diff_side(config: &aws_config::SdkConfig, side: String) -> eyre::Result<Node> {
+    let path = std::path::Path::new(&side);
+    if path.is_file() {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("reading snapshot {}", path.display()))?;
+        let mut roots: Vec<Node> = serde_json::from_str(&contents).wrap_err("parsing snapshot")?;
+        if roots.len() != 1 {
+            eyre::bail!(
+                "snapshot {} contains {} trees, expected a single load balancer",
+                path.display(),
+                roots.len()
+            );
+        }
+        return Ok(roots.swap_remove(0));
+    }
+
+    // panic safety: a load balancer is given, so there is no selection to abandon
+    let tree = alb::fetch_alb_tree(config, Some(side), Default::default()).await?;
+    Ok(tree.expect("load balancer given"))
+}
+
+/// Load the AWS configuration from the environment, with credentials for `--assume-role-arn` if
+/// it was given
+///
+/// The assumed role's credentials are refreshed from the environment's credentials shortly
+/// before they expire, so long running commands keep working. If the selected profile assumes a
+/// role that requires MFA, the code is prompted for here, before any picker is shown.
+async fn load_config(
+    cli: &Cli,
+    settings: &config::Config,
+    all_regions: bool,
+) -> eyre::Result<aws_config::SdkConfig> {
+    let loader = || {
+        let mut loader = aws_config::from_env().timeout_config(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_secs(cli.timeout))
+                .build(),
+        );
+        if let Some(region) = settings.region.clone().filter(|_| !all_regions) {
+            loader = loader.region(Region::new(region));
+        }
+        if let Some(profile) = &settings.profile {
+            loader = loader.profile_name(profile);
+        }
+        loader
+    };
+    let profile_name = settings.profile.as_deref().unwrap_or("default");
+    let config = if let Some(profile) = mfa::selected_profile(profile_name) {
+        let source = loader().profile_name(&profile.source_profile).load().await;
+        let token = mfa::prompt_token(&profile.mfa_serial)?;
+        let credentials = mfa::assume_role(&source, &profile, &token).await?;
+        loader().credentials_provider(credentials).load().await
+    } else {
+        loader().load().await
+    };
+
+    let Some(role_arn) = &cli.assume_role_arn else {
+        return Ok(config);
+    };
+    let mut provider = AssumeRoleProvider::builder(role_arn)
+        .session_name(&cli.session_name)
+        .configure(&config);
+    if let Some(external_id) = &cli.external_id {
+        provider = provider.external_id(external_id);
+    }
+    Ok(loader()
+        .credentials_provider(provider.build().await)
+        .load()
+        .await)
+}
+
+/// Options for a sweep of load balancers over several regions or profiles, which is only
+/// supported for `elbv2 --all` and only for rendered output
+fn sweep_options(cli: &Cli, output: OutputFormat, flag: &str) -> eyre::Result<alb::AlbOptions> {
+    let Some(Commands::Elbv2 {
+        all: true,
+        attributes,
+        waf,
+        tags,
+        sort,
+        show_empty,
+        health_retries,
+        only_unhealthy,
+        ..
+    }) = &cli.command
+    else {
+        eyre::bail!("{flag} is only supported for `elbv2 --all`");
+    };
+    if cli.count_only || output == OutputFormat::Model || cli.select.is_some() {
+        eyre::bail!("{flag} cannot be combined with --count-only, --output model or --select");
+    }
+    Ok(alb::AlbOptions {
+        test_request: None,
+        attributes: *attributes,
+        waf: *waf,
+        sort: *sort,
+        show_empty: *show_empty,
+        health_retries: *health_retries,
+        only_unhealthy: *only_unhealthy,
+        max_items: cli.max_items,
+        age: cli.age,
+        tags: *tags,
+    })
+}
+
+/// The configuration to look up a resource with, in the region its ARN names if that differs from
+/// the configured one
+fn arn_config(config: &aws_config::SdkConfig, region: Option<String>) -> aws_config::SdkConfig {
+    match region {
+        Some(region) => config.to_builder().region(Region::new(region)).build(),
+        None => config.clone(),
+    }
+}
+
+/// Fetch and display the resource chosen on the command line or interactively
+async fn run(cli: Cli, settings: &config::Config, writer: &dyn OutputWriter) -> eyre::Result<()> {
+    let output = settings.output;
+    let all_regions = settings.region.as_deref() == Some(ALL_REGIONS);
+
+    // every profile is loaded on its own, so a broken default profile does not stop the sweep
+    if cli.all_profiles {
+        if all_regions {
+            eyre::bail!("--all-profiles cannot be combined with --region {ALL_REGIONS}");
+        }
+        let options = sweep_options(&cli, output, "--all-profiles")?;
+        let mut profiles = Vec::new();
+        for profile in mfa::profile_names()? {
+            let settings = config::Config {
+                profile: Some(profile.clone()),
+                ..settings.clone()
+            };
+            match load_config(&cli, &settings, false).await {
+                Ok(config) => profiles.push((profile, config)),
+                Err(e) => tracing::warn!("profile {profile}: {e:#}, skipping"),
+            }
+        }
+        alb::display_all_albs_in_profiles(profiles, options, writer).await?;
+        return Ok(());
+    }
+
+    let config = load_config(&cli, settings, all_regions).await?;
+
+    if cli.count_only {
+        let estimate = count_only(&config, cli.command).await?;
+        eprintln!("{estimate}");
+        return Ok(());
+    }
+
+    if all_regions {
+        let options = sweep_options(&cli, output, &format!("--region {ALL_REGIONS}"))?;
+        alb::display_all_albs_in_all_regions(&config, options, writer).await?;
+        return Ok(());
+    }
+
+    if output == OutputFormat::Model || cli.select.is_some() {
+        let Some(Commands::Elbv2 {
+            load_balancer_arn,
+            name: None,
+            all: false,
+            attributes,
+            waf,
+            sort,
+            health_retries,
+            ..
+        }) = cli.command
+        else {
+            eyre::bail!(
+                "--output model and --select are only supported for a single load balancer"
+            );
+        };
+        // empty lists are already explicit in the model
+        let options = alb::AlbOptions {
+            test_request: None,
+            attributes,
+            waf,
+            sort,
+            show_empty: false,
+            health_retries,
+            only_unhealthy: false,
+            max_items: cli.max_items,
+            age: cli.age,
+            tags: false,
+        };
+        let model = alb::fetch_alb_model(&config, load_balancer_arn, options)
+            .await?
+            .ok_or(NothingSelected("load balancer"))?;
+        if let Some(path) = &cli.select {
+            for value in model.select(path)? {
+                writer.write_line(&value);
+            }
+            return Ok(());
+        }
+        let json = if cli.json_pretty {
+            serde_json::to_string_pretty(&model)
+        } else {
+            serde_json::to_string(&model)
+        };
+        writer.write_line(&json.wrap_err("serializing model")?);
+        return Ok(());
+    }
 
     match cli.command {
-        Some(Commands::Elbv2 { load_balancer_arn }) => {
-            alb::display_alb(&config, load_balancer_arn, &writer).await?;
+        Some(Commands::Elbv2 {
+            load_balancer_arn,
+            name,
+            all,
+            test_request,
+            method,
+            attributes,
+            waf,
+            tags,
+            sort,
+            oneline,
+            show_empty,
+            health_retries,
+            only_unhealthy,
+        }) => {
+            let options = alb::AlbOptions {
+                test_request: test_request
+                    .map(|url| alb::TestRequest::parse(&url, &method))
+                    .transpose()?,
+                attributes,
+                waf,
+                sort,
+                show_empty,
+                health_retries,
+                only_unhealthy,
+                max_items: cli.max_items,
+                age: cli.age,
+                tags,
+            };
+            if oneline && (all || name.is_some()) {
+                alb::display_alb_summaries(&config, name.as_deref(), options, writer).await?;
+            } else if oneline {
+                let model = alb::fetch_alb_model(&config, load_balancer_arn, options)
+                    .await?
+                    .ok_or(NothingSelected("load balancer"))?;
+                writer.write_line(&model.summary());
+            } else if all {
+                alb::display_all_albs(&config, options, writer).await?;
+            } else if let Some(pattern) = name {
+                alb::display_albs_by_name(&config, &pattern, options, writer).await?;
+            } else {
+                alb::display_alb(&config, load_balancer_arn, options, writer).await?;
+            }
         }
         Some(Commands::ApiGateway { api_id }) => {
-            apigateway::display_apigateway(&config, api_id, &writer).await?;
+            apigateway::display_apigateway(&config, api_id, writer).await?;
         }
         Some(Commands::Ecs {
             cluster_arn,
             service_arn,
+            image_digests,
+            instances,
+            stopped_within,
         }) => {
-            ecs::display_ecs(&config, cluster_arn, service_arn, &writer).await?;
+            let options = ecs::EcsOptions {
+                image_digests,
+                instances,
+                max_items: cli.max_items,
+                age: cli.age,
+                stopped_within,
+            };
+            ecs::display_ecs(&config, cluster_arn, service_arn, options, writer).await?;
+        }
+        Some(Commands::Completions { .. }) => {
+            unreachable!("completions are written before AWS is configured")
+        }
+        Some(Commands::Diff { old, new }) => {
+            // one at a time, so their progress spinners do not draw over each other
+            let old = load_diff_side(&config, old).await?;
+            let new = load_diff_side(&config, new).await?;
+            diff::write_diff(&old, &new, writer);
+        }
+        Some(Commands::Limits) => {
+            limits::display_account_limits(&config, writer).await?;
+        }
+        Some(Commands::TargetGroup {
+            arn,
+            attributes,
+            sort,
+            health_retries,
+            only_unhealthy,
+            show_empty,
+        }) => {
+            let options = alb::AlbOptions {
+                attributes,
+                sort,
+                health_retries,
+                only_unhealthy,
+                show_empty,
+                ..alb::AlbOptions::default()
+            };
+            alb::display_target_group(&config, &arn, options, writer).await?;
+        }
+        Some(Commands::Resource { arn }) => {
+            let parsed = arn::parse_arn(&arn)?;
+            let config = arn_config(&config, parsed.region);
+            match parsed.target {
+                ArnTarget::LoadBalancer(arn) => {
+                    let options = alb::AlbOptions {
+                        max_items: cli.max_items,
+                        age: cli.age,
+                        ..alb::AlbOptions::default()
+                    };
+                    alb::display_alb(&config, Some(arn), options, writer).await?;
+                }
+                ArnTarget::TargetGroup(arn) => {
+                    alb::display_target_group(&config, &arn, Default::default(), writer).await?;
+                }
+                ArnTarget::EcsCluster(cluster) => {
+                    let options = ecs::EcsOptions {
+                        max_items: cli.max_items,
+                        age: cli.age,
+                        ..Default::default()
+                    };
+                    ecs::display_ecs(&config, Some(cluster), None, options, writer).await?;
+                }
+                ArnTarget::EcsService { cluster, service } => {
+                    let options = ecs::EcsOptions {
+                        max_items: cli.max_items,
+                        age: cli.age,
+                        ..Default::default()
+                    };
+                    ecs::display_ecs(&config, Some(cluster), Some(service), options, writer)
+                        .await?;
+                }
+                ArnTarget::RestApi(id) => {
+                    apigateway::display_apigateway(&config, Some(id), writer).await?;
+                }
+            }
         }
         None => {
             // No subcommand provided, show resource type selection
             match select_resource_type()? {
                 Some(ResourceType::Alb) => {
-                    alb::display_alb(&config, None, &writer).await?;
+                    let options = alb::AlbOptions {
+                        max_items: cli.max_items,
+                        age: cli.age,
+                        ..Default::default()
+                    };
+                    alb::display_alb(&config, None, options, writer).await?;
                 }
                 Some(ResourceType::ApiGateway) => {
-                    apigateway::display_apigateway(&config, None, &writer).await?;
+                    apigateway::display_apigateway(&config, None, writer).await?;
                 }
                 Some(ResourceType::Ecs) => {
-                    ecs::display_ecs(&config, None, None, &writer).await?;
-                }
-                None => {
-                    eprintln!("No resource type selected");
-                    std::process::exit(1);
+                    let options = ecs::EcsOptions {
+                        max_items: cli.max_items,
+                        age: cli.age,
+                        ..Default::default()
+                    };
+                    ecs::display_ecs(&config, None, None, options, writer).await?;
                 }
+                None => return Err(NothingSelected("resource type").into()),
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_from_stdin_fills_service_when_cluster_given() {
+        let mut cli = Cli::try_parse_from(["lbtree", "ecs", "--cluster-arn", "prod"]).unwrap();
+        select_from_stdin(&mut cli.command, "\n  web  \n".as_bytes()).unwrap();
+        let Some(Commands::Ecs {
+            cluster_arn,
+            service_arn,
+            ..
+        }) = cli.command
+        else {
+            panic!("expected the ecs subcommand");
+        };
+        assert_eq!(cluster_arn.as_deref(), Some("prod"));
+        assert_eq!(service_arn.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn test_select_from_stdin_rejects_empty_input() {
+        let mut cli = Cli::try_parse_from(["lbtree", "elbv2"]).unwrap();
+        let err = select_from_stdin(&mut cli.command, "".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("empty"), "{err}");
+    }
+}
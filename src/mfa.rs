@@ -0,0 +1,199 @@
+use aws_config::SdkConfig;
+use aws_sdk_sts::config::Credentials;
+use color_eyre::eyre::{self, Context};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tracing::{Instrument, info_span};
+
+/// A profile that assumes a role whose trust policy requires MFA
+///
+/// The SDK cannot prompt for the token itself, so these profiles are handled here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MfaProfile {
+    pub role_arn: String,
+    pub mfa_serial: String,
+    /// Profile with the long-lived credentials used to assume the role
+    pub source_profile: String,
+    pub external_id: Option<String>,
+    pub session_name: Option<String>,
+}
+
+/// `$AWS_CONFIG_FILE`, or `~/.aws/config`
+fn config_file_path() -> Option<PathBuf> {
+    std::env::var_os("AWS_CONFIG_FILE")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".aws").join("config"))
+        })
+}
+
+/// Find a profile in the shared config file, if it needs an MFA code
+pub fn selected_profile(name: &str) -> Option<MfaProfile> {
+    let contents = std::fs::read_to_string(config_file_path()?).ok()?;
+    parse_profile(&contents, name)
+}
+
+/// Names of every profile in the shared config file, in the order they are defined
+pub fn profile_names() -> eyre::Result<Vec<String>> {
+    let path = config_file_path().ok_or_else(|| eyre::eyre!("no home directory"))?;
+    let contents = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("reading AWS config file {}", path.display()))?;
+    Ok(parse_profile_names(&contents))
+}
+
+/// Read the profile names from the contents of a shared config file, skipping other sections
+/// such as `[sso-session ...]`
+fn parse_profile_names(contents: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in contents.lines() {
+        let Some(section) = line
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+        else {
+            continue;
+        };
+        let name = match section.trim() {
+            "default" => "default",
+            section => match section.strip_prefix("profile ") {
+                Some(name) => name.trim(),
+                None => continue,
+            },
+        };
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Read a profile from the contents of a shared config file, returning it only if it assumes a
+/// role with an MFA device
+fn parse_profile(contents: &str, name: &str) -> Option<MfaProfile> {
+    let mut in_profile = false;
+    let mut values = std::collections::HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let section = section.trim();
+            in_profile =
+                section == format!("profile {name}") || (section == name && name == "default");
+            continue;
+        }
+        if !in_profile {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    Some(MfaProfile {
+        role_arn: values.remove("role_arn")?,
+        mfa_serial: values.remove("mfa_serial")?,
+        source_profile: values.remove("source_profile")?,
+        external_id: values.remove("external_id"),
+        session_name: values.remove("role_session_name"),
+    })
+}
+
+/// Ask for the current code of an MFA device on the terminal
+///
+/// The terminal is opened directly so the prompt works when stdout is piped, and it is done before
+/// any picker is shown so the two never share the screen.
+pub fn prompt_token(mfa_serial: &str) -> eyre::Result<String> {
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .wrap_err("opening the terminal to prompt for an MFA code")?;
+    write!(tty, "MFA code for {mfa_serial}: ")?;
+    tty.flush()?;
+    let mut token = String::new();
+    BufReader::new(tty).read_line(&mut token)?;
+    Ok(token.trim().to_string())
+}
+
+/// Assume the role of an MFA profile with the given token, using the source profile's credentials
+pub async fn assume_role(
+    source: &SdkConfig,
+    profile: &MfaProfile,
+    token: &str,
+) -> eyre::Result<Credentials> {
+    let client = aws_sdk_sts::Client::new(source);
+    let response = client
+        .assume_role()
+        .role_arn(&profile.role_arn)
+        .role_session_name(profile.session_name.as_deref().unwrap_or("lbtree"))
+        .set_external_id(profile.external_id.clone())
+        .serial_number(&profile.mfa_serial)
+        .token_code(token)
+        .send()
+        .instrument(info_span!("assume_role"))
+        .await
+        .wrap_err_with(|| format!("assuming role {} with MFA", profile.role_arn))?;
+    let credentials = response
+        .credentials()
+        .ok_or_else(|| eyre::eyre!("no credentials returned for role {}", profile.role_arn))?;
+    Ok(Credentials::new(
+        credentials.access_key_id(),
+        credentials.secret_access_key(),
+        Some(credentials.session_token().to_string()),
+        SystemTime::try_from(*credentials.expiration()).ok(),
+        "lbtree-mfa",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+[default]
+region = us-east-1
+
+[profile admin]
+role_arn = arn:aws:iam::123456789012:role/admin
+mfa_serial = arn:aws:iam::000000000000:mfa/me
+source_profile = default
+role_session_name = me
+
+[profile readonly]
+role_arn = arn:aws:iam::123456789012:role/readonly
+source_profile = default
+";
+
+    #[test]
+    fn test_parse_profile_names() {
+        let contents = format!("{CONFIG}\n[sso-session corp]\nsso_region = us-east-1\n");
+        assert_eq!(
+            parse_profile_names(&contents),
+            vec!["default", "admin", "readonly"]
+        );
+    }
+
+    #[test]
+    fn test_parse_profile_requiring_mfa() {
+        assert_eq!(
+            parse_profile(CONFIG, "admin"),
+            Some(MfaProfile {
+                role_arn: "arn:aws:iam::123456789012:role/admin".to_string(),
+                mfa_serial: "arn:aws:iam::000000000000:mfa/me".to_string(),
+                source_profile: "default".to_string(),
+                external_id: None,
+                session_name: Some("me".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_profile_without_mfa() {
+        assert_eq!(parse_profile(CONFIG, "readonly"), None);
+        assert_eq!(parse_profile(CONFIG, "default"), None);
+        assert_eq!(parse_profile(CONFIG, "missing"), None);
+    }
+}
@@ -0,0 +1,380 @@
+use aws_sdk_elasticloadbalancingv2::types::{
+    Action, Listener, LoadBalancer, Rule, TargetGroup, TargetHealthDescription,
+};
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the typed output, increased whenever a field is removed or changes meaning
+///
+/// Adding a field is not a breaking change, so consumers should ignore fields they do not know.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A load balancer with its listeners and target groups, written by `--output model`
+///
+/// Unlike [`crate::tree::Node`], whose content is the human readable line, every value is a
+/// separate field so downstream tooling does not have to parse the rendered tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlbTree {
+    pub schema_version: u32,
+    pub load_balancer: LoadBalancerNode,
+    pub listeners: Vec<ListenerNode>,
+    pub target_groups: Vec<TargetGroupNode>,
+}
+
+impl AlbTree {
+    /// One line summary for `--oneline`: name, scheme, type, number of listeners and number of
+    /// targets with the healthy ones in brackets, separated by spaces for `awk`
+    pub fn summary(&self) -> String {
+        let lb = &self.load_balancer;
+        let targets: usize = self.target_groups.iter().map(|tg| tg.targets.len()).sum();
+        let healthy: usize = self.target_groups.iter().map(|tg| tg.healthy).sum();
+        format!(
+            "{name} {scheme} {lb_type} {listeners} {targets}({healthy})",
+            name = lb.name.as_deref().unwrap_or("unknown"),
+            scheme = lb.scheme.as_deref().unwrap_or("unknown"),
+            lb_type = lb.r#type.as_deref().unwrap_or("unknown"),
+            listeners = self.listeners.len(),
+        )
+    }
+
+    /// Values of the field a dotted path leads to, for `--select`, e.g. `target.state` or
+    /// `load_balancer.dns_name`
+    ///
+    /// The path is made of field names, where lists are stepped into so `listeners.port` gives
+    /// the port of every listener. It can also start with the kind of a node wherever it is in
+    /// the tree: `listener`, `rule`, `action`, `target_group` or `target`. Dashes may be used
+    /// instead of underscores. Unset values are left out and strings are written without quotes.
+    pub fn select(&self, path: &str) -> eyre::Result<Vec<String>> {
+        let root = serde_json::to_value(self)?;
+        let path = path.replace('-', "_");
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let mut values = match first {
+            "listener" => field(&[&root], "listeners")?,
+            "rule" => field(&field(&[&root], "listeners")?, "rules")?,
+            "action" => field(&field(&field(&[&root], "listeners")?, "rules")?, "actions")?,
+            "target_group" => field(&[&root], "target_groups")?,
+            "target" => field(&field(&[&root], "target_groups")?, "targets")?,
+            key => field(&[&root], key)?,
+        };
+        for key in segments {
+            values = field(&values, key)?;
+        }
+        Ok(values
+            .into_iter()
+            .filter(|value| !value.is_null())
+            .map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect())
+    }
+}
+
+/// The field `key` of each value, with lists stepped into
+///
+/// An error is returned if none of the values has the field, as it is likely a typo.
+fn field<'a>(values: &[&'a Value], key: &str) -> eyre::Result<Vec<&'a Value>> {
+    if key.is_empty() {
+        eyre::bail!("empty field name in --select path");
+    }
+    if !values.is_empty() && !values.iter().any(|value| value.get(key).is_some()) {
+        let known: Vec<&str> = values
+            .iter()
+            .filter_map(|value| value.as_object())
+            .flat_map(|object| object.keys().map(|k| k.as_str()))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        eyre::bail!("no field {key:?}, expected one of: {}", known.join(", "));
+    }
+    Ok(values
+        .iter()
+        .filter_map(|value| value.get(key))
+        .flat_map(|value| match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadBalancerNode {
+    pub arn: Option<String>,
+    pub name: Option<String>,
+    pub dns_name: Option<String>,
+    /// `application`, `network` or `gateway`
+    pub r#type: Option<String>,
+    pub scheme: Option<String>,
+    pub state: Option<String>,
+}
+
+impl From<&LoadBalancer> for LoadBalancerNode {
+    fn from(lb: &LoadBalancer) -> Self {
+        Self {
+            arn: lb.load_balancer_arn().map(|s| s.to_string()),
+            name: lb.load_balancer_name().map(|s| s.to_string()),
+            dns_name: lb.dns_name().map(|s| s.to_string()),
+            r#type: lb.r#type().map(|t| t.as_str().to_string()),
+            scheme: lb.scheme().map(|s| s.as_str().to_string()),
+            state: lb
+                .state()
+                .and_then(|s| s.code())
+                .map(|c| c.as_str().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListenerNode {
+    pub arn: Option<String>,
+    pub protocol: Option<String>,
+    pub port: Option<i32>,
+    /// Rules in the order the load balancer evaluates them, ending with the default rule
+    pub rules: Vec<RuleNode>,
+}
+
+impl ListenerNode {
+    pub fn new(listener: &Listener, rules: &[Rule]) -> Self {
+        Self {
+            arn: listener.listener_arn().map(|s| s.to_string()),
+            protocol: listener.protocol().map(|p| p.as_str().to_string()),
+            port: listener.port(),
+            rules: rules.iter().map(RuleNode::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleNode {
+    pub arn: Option<String>,
+    /// `default` for the default rule
+    pub priority: Option<String>,
+    pub is_default: bool,
+    pub actions: Vec<ActionNode>,
+}
+
+impl From<&Rule> for RuleNode {
+    fn from(rule: &Rule) -> Self {
+        Self {
+            arn: rule.rule_arn().map(|s| s.to_string()),
+            priority: rule.priority().map(|s| s.to_string()),
+            is_default: rule.is_default().unwrap_or_default(),
+            actions: crate::alb::ordered_actions(rule)
+                .into_iter()
+                .map(ActionNode::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionNode {
+    /// e.g. `forward` or `redirect`
+    pub r#type: Option<String>,
+    /// Position among the actions of the rule, which are listed in this order
+    pub order: Option<i32>,
+    /// Target groups requests are forwarded to, empty for other action types
+    pub target_group_arns: Vec<String>,
+}
+
+impl From<&Action> for ActionNode {
+    fn from(action: &Action) -> Self {
+        Self {
+            r#type: action.r#type().map(|t| t.as_str().to_string()),
+            order: action.order(),
+            target_group_arns: crate::alb::forward_target_group_arns(action)
+                .into_iter()
+                .map(|arn| arn.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetGroupNode {
+    pub arn: Option<String>,
+    pub name: Option<String>,
+    pub protocol: Option<String>,
+    pub port: Option<i32>,
+    pub healthy: usize,
+    pub targets: Vec<TargetNode>,
+}
+
+impl TargetGroupNode {
+    pub fn new(target_group: &TargetGroup, targets: &[TargetHealthDescription]) -> Self {
+        let targets: Vec<TargetNode> = targets.iter().map(TargetNode::from).collect();
+        Self {
+            arn: target_group.target_group_arn().map(|s| s.to_string()),
+            name: target_group.target_group_name().map(|s| s.to_string()),
+            protocol: target_group.protocol().map(|p| p.as_str().to_string()),
+            port: target_group.port(),
+            healthy: targets
+                .iter()
+                .filter(|t| t.state.as_deref() == Some("healthy"))
+                .count(),
+            targets,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetNode {
+    /// Instance id, IP address or Lambda function ARN
+    pub id: Option<String>,
+    pub port: Option<i32>,
+    /// e.g. `healthy`, `unhealthy` or `draining`
+    pub state: Option<String>,
+}
+
+impl From<&TargetHealthDescription> for TargetNode {
+    fn from(target: &TargetHealthDescription) -> Self {
+        Self {
+            id: target
+                .target()
+                .and_then(|t| t.id())
+                .map(|id| id.to_string()),
+            port: target.target().and_then(|t| t.port()),
+            state: target
+                .target_health()
+                .and_then(|h| h.state())
+                .map(|s| s.as_str().to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_elasticloadbalancingv2::types::{
+        ActionTypeEnum, TargetDescription, TargetHealth, TargetHealthStateEnum,
+    };
+
+    #[test]
+    fn test_target_group_node_counts_healthy_targets() {
+        let target_group = TargetGroup::builder()
+            .target_group_arn("arn:tg")
+            .target_group_name("web")
+            .port(80)
+            .build();
+        let target = |id: &str, state| {
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id(id).port(80).build())
+                .target_health(TargetHealth::builder().state(state).build())
+                .build()
+        };
+        let node = TargetGroupNode::new(
+            &target_group,
+            &[
+                target("i-1", TargetHealthStateEnum::Healthy),
+                target("i-2", TargetHealthStateEnum::Unhealthy),
+            ],
+        );
+        assert_eq!(node.name.as_deref(), Some("web"));
+        assert_eq!(node.healthy, 1);
+        assert_eq!(node.targets.len(), 2);
+        assert_eq!(node.targets[1].state.as_deref(), Some("unhealthy"));
+    }
+
+    #[test]
+    fn test_summary() {
+        let tree = AlbTree {
+            schema_version: SCHEMA_VERSION,
+            load_balancer: LoadBalancerNode {
+                arn: None,
+                name: Some("web".to_string()),
+                dns_name: None,
+                r#type: Some("application".to_string()),
+                scheme: Some("internet-facing".to_string()),
+                state: None,
+            },
+            listeners: Vec::new(),
+            target_groups: vec![TargetGroupNode {
+                arn: None,
+                name: None,
+                protocol: None,
+                port: None,
+                healthy: 1,
+                targets: vec![
+                    TargetNode {
+                        id: None,
+                        port: None,
+                        state: Some("healthy".to_string()),
+                    };
+                    2
+                ],
+            }],
+        };
+        assert_eq!(tree.summary(), "web internet-facing application 0 2(1)");
+    }
+
+    #[test]
+    fn test_select() {
+        let target = |id: &str, state: &str| TargetNode {
+            id: Some(id.to_string()),
+            port: Some(80),
+            state: Some(state.to_string()),
+        };
+        let tree = AlbTree {
+            schema_version: SCHEMA_VERSION,
+            load_balancer: LoadBalancerNode {
+                arn: None,
+                name: Some("web".to_string()),
+                dns_name: Some("web.example.com".to_string()),
+                r#type: None,
+                scheme: None,
+                state: None,
+            },
+            listeners: vec![ListenerNode {
+                arn: None,
+                protocol: Some("HTTP".to_string()),
+                port: Some(80),
+                rules: Vec::new(),
+            }],
+            target_groups: vec![TargetGroupNode {
+                arn: None,
+                name: Some("app".to_string()),
+                protocol: None,
+                port: None,
+                healthy: 1,
+                targets: vec![target("i-1", "healthy"), target("i-2", "draining")],
+            }],
+        };
+
+        assert_eq!(
+            tree.select("target.state").unwrap(),
+            ["healthy", "draining"]
+        );
+        assert_eq!(
+            tree.select("target_groups.targets.id").unwrap(),
+            ["i-1", "i-2"]
+        );
+        assert_eq!(tree.select("listener.port").unwrap(), ["80"]);
+        assert_eq!(
+            tree.select("load-balancer.dns-name").unwrap(),
+            ["web.example.com"]
+        );
+        // unset values and empty lists give nothing
+        assert!(tree.select("load_balancer.arn").unwrap().is_empty());
+        assert!(tree.select("rule.priority").unwrap().is_empty());
+
+        let err = tree.select("target.status").unwrap_err();
+        assert!(err.to_string().contains("no field \"status\""));
+        assert!(err.to_string().contains("state"));
+        assert!(tree.select("target.").is_err());
+    }
+
+    #[test]
+    fn test_action_node_serializes_type_field() {
+        let action = Action::builder()
+            .r#type(ActionTypeEnum::Forward)
+            .target_group_arn("arn:tg")
+            .build();
+        let json = serde_json::to_value(ActionNode::from(&action)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "forward", "order": null, "target_group_arns": ["arn:tg"]})
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// When output should be sent through a pager
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Paging {
+    /// Only when stdout is a terminal and the output does not fit on screen
+    #[default]
+    Auto,
+    /// Whenever stdout is a terminal
+    Always,
+    Never,
+}
+
+/// Number of rows of the terminal attached to stdout, if any
+pub fn terminal_height() -> Option<usize> {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(rows, _)| rows as usize)
+}
+
+/// Show lines in `$PAGER`, or `less -R` if it is not set, waiting until the pager exits
+///
+/// `less` is always given `-R` so colours are shown rather than escaped.
+pub fn page(lines: &[String]) -> std::io::Result<()> {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    // panic safety: the pager command was checked to not be blank
+    let program = parts.next().expect("pager command");
+    let mut args: Vec<&str> = parts.collect();
+    if program == "less" && !args.contains(&"-R") {
+        args.push("-R");
+    }
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for line in lines {
+            // the user can quit the pager before reading everything
+            if writeln!(stdin, "{line}").is_err() {
+                break;
+            }
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
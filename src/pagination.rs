@@ -0,0 +1,20 @@
+use aws_smithy_async::future::pagination_stream::PaginationStream;
+use color_eyre::eyre::{self, Context};
+
+/// Drain an item paginator, e.g. `client.list_tasks().into_paginator().items().send()`, into a
+/// `Vec`
+///
+/// The error from the first page that fails is returned with `context` attached.
+pub async fn collect_all<T, E>(
+    mut items: PaginationStream<Result<T, E>>,
+    context: &'static str,
+) -> eyre::Result<Vec<T>>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut out = Vec::new();
+    while let Some(item) = items.next().await {
+        out.push(item.context(context)?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,51 @@
+use crossbeam::channel::TrySendError;
+use skim::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of items fetched ahead of what skim has read, so very large lists do not pile up in
+/// memory while the user is still typing
+pub const PICKER_CHANNEL_CAPACITY: usize = 1024;
+
+/// How long to wait before offering an item again while skim's channel is full
+const FULL_CHANNEL_DELAY: Duration = Duration::from_millis(20);
+
+/// Send an item to skim, waiting without blocking the runtime while the channel is full
+///
+/// Returns `false` once skim has exited, so the caller can stop fetching items nobody will see.
+pub async fn send_to_picker(tx: &SkimItemSender, item: Arc<dyn SkimItem>) -> bool {
+    let mut item = item;
+    loop {
+        match tx.try_send(item) {
+            Ok(()) => return true,
+            Err(TrySendError::Full(returned)) => {
+                item = returned;
+                tokio::time::sleep(FULL_CHANNEL_DELAY).await;
+            }
+            Err(TrySendError::Disconnected(_)) => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::bounded;
+
+    #[tokio::test]
+    async fn test_send_waits_for_room_and_stops_when_closed() {
+        let (tx, rx): (SkimItemSender, SkimItemReceiver) = bounded(1);
+        assert!(send_to_picker(&tx, Arc::new("a".to_string())).await);
+
+        // the second item only fits once the first has been read
+        let reader = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            assert_eq!(rx.recv().unwrap().text(), "a");
+        });
+        assert!(send_to_picker(&tx, Arc::new("b".to_string())).await);
+        reader.join().unwrap();
+
+        // the receiver is gone, as when the user closes skim
+        assert!(!send_to_picker(&tx, Arc::new("c".to_string())).await);
+    }
+}
@@ -1,36 +1,488 @@
-use std::sync::{Arc, Mutex};
+use crossbeam::channel::{Receiver, Sender, unbounded};
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::pager::{self, Paging};
+use crate::tree::{INDENT_WIDTH, Node, TreeBuilder};
 
 /// Trait for writing output, allows abstraction for testing
 pub trait OutputWriter: Send + Sync {
     fn write_line(&self, content: &str);
+
+    /// Write a resource at the given indentation level
+    ///
+    /// Writers that build a graph use the indent to track parent/child relationships; by default
+    /// the node is written as an indented tree line.
+    fn write_node(&self, indent: usize, content: &str) {
+        let prefix = " ".repeat(indent) + "-> ";
+        self.write_line(&format!("{}{}", prefix, content));
+    }
+
+    /// Write a resource along with its ARN, or id for resources without one
+    ///
+    /// Most writers only show the content, so by default this is the same as `write_node`.
+    fn write_resource(&self, indent: usize, content: &str, _arn: Option<&str>) {
+        self.write_node(indent, content);
+    }
+
+    /// Separate two top-level trees, e.g. when several resources are displayed
+    ///
+    /// By default a blank line is written; writers with structured output ignore it.
+    fn write_separator(&self) {
+        self.write_line("");
+    }
+
+    /// Flush any buffered output, called once rendering is complete
+    fn flush(&self) {}
+}
+
+impl OutputWriter for Box<dyn OutputWriter> {
+    fn write_line(&self, content: &str) {
+        self.as_ref().write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.as_ref().write_node(indent, content);
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        self.as_ref().write_resource(indent, content, arn);
+    }
+
+    fn write_separator(&self) {
+        self.as_ref().write_separator();
+    }
+
+    fn flush(&self) {
+        self.as_ref().flush();
+    }
 }
 
-/// Standard output writer using println!
-pub struct StdoutWriter;
+/// Standard output writer
+///
+/// Lines are collected in a `BufWriter` so stdout is only locked and written when the buffer
+/// fills or the writer is flushed, rather than once per line. `StdoutLock` is not `Send`, so the
+/// buffer wraps the `Stdout` handle itself to keep the writer usable across tasks.
+///
+/// When paging, lines are held back until the writer is flushed, since only then is it known
+/// whether the output fits on screen.
+pub struct StdoutWriter {
+    out: Mutex<BufWriter<Stdout>>,
+    paging: Paging,
+    /// lines waiting to be paged, `None` if output is never paged
+    held: Option<Mutex<Vec<String>>>,
+}
+
+impl StdoutWriter {
+    pub fn new() -> Self {
+        Self::with_paging(Paging::Never)
+    }
+
+    /// Write to stdout, or through a pager if stdout is a terminal
+    pub fn with_paging(paging: Paging) -> Self {
+        let held = (paging != Paging::Never && std::io::stdout().is_terminal())
+            .then(|| Mutex::new(Vec::new()));
+        Self {
+            out: Mutex::new(BufWriter::new(std::io::stdout())),
+            paging,
+            held,
+        }
+    }
+}
+
+impl Default for StdoutWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl OutputWriter for StdoutWriter {
     fn write_line(&self, content: &str) {
-        println!("{}", content);
+        if let Some(held) = &self.held {
+            held.lock().unwrap().push(content.to_string());
+            return;
+        }
+        let mut out = self.out.lock().unwrap();
+        // ignore write errors, e.g. a closed pipe when output is sent to `head`
+        let _ = writeln!(out, "{}", content);
+    }
+
+    fn flush(&self) {
+        let mut out = self.out.lock().unwrap();
+        if let Some(held) = &self.held {
+            let lines = std::mem::take(&mut *held.lock().unwrap());
+            let fits = pager::terminal_height().is_some_and(|height| lines.len() < height);
+            let page = !lines.is_empty() && (self.paging == Paging::Always || !fits);
+            // fall back to writing directly if the pager cannot be started
+            if !(page && pager::page(&lines).is_ok()) {
+                for line in &lines {
+                    let _ = writeln!(out, "{}", line);
+                }
+            }
+        }
+        let _ = out.flush();
+    }
+}
+
+impl Drop for StdoutWriter {
+    fn drop(&mut self) {
+        OutputWriter::flush(self);
+    }
+}
+
+/// Tree writer that indents each level by a chosen number of spaces, e.g. from `--indent`
+///
+/// Resources are written with an indent of [`INDENT_WIDTH`] per level of the tree, which this
+/// converts back to a depth before applying its own width.
+pub struct IndentWriter<W: OutputWriter> {
+    inner: W,
+    width: usize,
+}
+
+impl<W: OutputWriter> IndentWriter<W> {
+    pub fn new(inner: W, width: usize) -> Self {
+        Self { inner, width }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for IndentWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner.write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.inner
+            .write_node(indent / INDENT_WIDTH * self.width, content);
+    }
+
+    fn write_separator(&self) {
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Tree writer without the `-> ` prefix, for embedding in tools that add their own formatting
+///
+/// Each level is still indented by exactly `width` spaces, so depth can be recovered from the
+/// leading whitespace, and an optional marker goes between the indent and the content.
+pub struct PlainWriter<W: OutputWriter> {
+    inner: W,
+    width: usize,
+    marker: String,
+}
+
+impl<W: OutputWriter> PlainWriter<W> {
+    pub fn new(inner: W, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            marker: String::new(),
+        }
+    }
+
+    /// Write `marker` before the content of every resource, e.g. `"* "`
+    pub fn marker(mut self, marker: impl Into<String>) -> Self {
+        self.marker = marker.into();
+        self
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for PlainWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner.write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        let indent = " ".repeat(indent / INDENT_WIDTH * self.width);
+        self.inner
+            .write_line(&format!("{indent}{}{content}", self.marker));
+    }
+
+    fn write_separator(&self) {
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that appends the ARN, or id, of each resource to its line in parentheses, e.g. from
+/// `--show-arns`
+pub struct ShowArnsWriter<W: OutputWriter> {
+    inner: W,
+}
+
+impl<W: OutputWriter> ShowArnsWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for ShowArnsWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner.write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.inner.write_node(indent, content);
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        match arn {
+            Some(arn) => {
+                self.inner
+                    .write_resource(indent, &format!("{content} ({arn})"), Some(arn))
+            }
+            None => self.inner.write_resource(indent, content, arn),
+        }
+    }
+
+    fn write_separator(&self) {
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that starts every line with a bracketed label, e.g. `[prod/eu-west-1] ` from
+/// `--context`, so output from several runs collected in one log can still be told apart
+///
+/// It wraps the final output rather than a formatting writer, so it sees every line as written.
+/// Separators are passed through as they are, leaving blank lines between trees blank.
+pub struct PrefixWriter<W: OutputWriter> {
+    inner: W,
+    prefix: String,
+}
+
+impl<W: OutputWriter> PrefixWriter<W> {
+    pub fn new(inner: W, label: &str) -> Self {
+        Self {
+            inner,
+            prefix: format!("[{label}] "),
+        }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for PrefixWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner
+            .write_line(&format!("{}{}", self.prefix, content));
+    }
+
+    fn write_separator(&self) {
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that drops every resource deeper than a maximum depth, e.g. from `--depth`
+///
+/// The root of a tree is at depth 0, so a maximum depth of 0 only shows the roots.
+pub struct MaxDepthWriter<W: OutputWriter> {
+    inner: W,
+    max_depth: usize,
+}
+
+impl<W: OutputWriter> MaxDepthWriter<W> {
+    pub fn new(inner: W, max_depth: usize) -> Self {
+        Self { inner, max_depth }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for MaxDepthWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner.write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        if indent / INDENT_WIDTH <= self.max_depth {
+            self.inner.write_node(indent, content);
+        }
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        if indent / INDENT_WIDTH <= self.max_depth {
+            self.inner.write_resource(indent, content, arn);
+        }
+    }
+
+    fn write_separator(&self) {
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that shows at most a number of resources under each parent, and as many roots, e.g.
+/// from `--max-items`
+///
+/// The resources left out, with everything beneath them, are replaced by a single
+/// `... (truncated, N more)` line once their siblings are done.
+pub struct MaxItemsWriter<W: OutputWriter> {
+    inner: W,
+    max_items: usize,
+    state: Mutex<MaxItemsState>,
+}
+
+#[derive(Default)]
+struct MaxItemsState {
+    /// (resources seen, resources left out) of the sibling group open at each depth
+    groups: Vec<(usize, usize)>,
+    /// Depth of the resource being left out, whose descendants are left out silently
+    hidden: Option<usize>,
+}
+
+impl<W: OutputWriter> MaxItemsWriter<W> {
+    pub fn new(inner: W, max_items: usize) -> Self {
+        Self {
+            inner,
+            max_items,
+            state: Mutex::new(MaxItemsState::default()),
+        }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Close the sibling groups deeper than `depth`, noting how many resources each left out
+    fn close_groups(&self, state: &mut MaxItemsState, depth: usize) {
+        while state.groups.len() > depth {
+            // panic safety: the loop only runs while there is a group to pop
+            let (_, left_out) = state.groups.pop().unwrap();
+            if left_out > 0 {
+                self.inner.write_node(
+                    state.groups.len() * INDENT_WIDTH,
+                    &format!("... (truncated, {left_out} more)"),
+                );
+            }
+        }
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for MaxItemsWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.inner.write_line(content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.write_resource(indent, content, None);
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        let depth = indent / INDENT_WIDTH;
+        let mut state = self.state.lock().unwrap();
+        if state.hidden.is_some_and(|hidden| depth > hidden) {
+            return;
+        }
+        state.hidden = None;
+        self.close_groups(&mut state, depth + 1);
+        state.groups.resize(depth + 1, (0, 0));
+        let group = &mut state.groups[depth];
+        group.0 += 1;
+        if group.0 > self.max_items {
+            group.1 += 1;
+            state.hidden = Some(depth);
+            return;
+        }
+        self.inner.write_resource(indent, content, arn);
+    }
+
+    fn write_separator(&self) {
+        // roots are counted across trees, so the separators of left out roots are left out too
+        let mut state = self.state.lock().unwrap();
+        if state.hidden == Some(0) {
+            return;
+        }
+        self.close_groups(&mut state, 1);
+        self.inner.write_separator();
+    }
+
+    fn flush(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.close_groups(&mut state, 0);
+        state.hidden = None;
+        drop(state);
+        self.inner.flush();
     }
 }
 
 /// Buffer writer for capturing output in tests
+///
+/// Lines are pushed onto a lock-free channel so concurrent writers never contend on a shared
+/// lock; they are only collected into a single string when the output is read back. Lines sent
+/// from the same thread keep their relative order.
 #[allow(dead_code)]
 pub struct BufferWriter {
-    buffer: Arc<Mutex<String>>,
+    tx: Sender<String>,
+    rx: Receiver<String>,
+    buffer: Mutex<String>,
 }
 
 impl BufferWriter {
     #[allow(dead_code)]
     pub fn new() -> Self {
+        let (tx, rx) = unbounded();
         Self {
-            buffer: Arc::new(Mutex::new(String::new())),
+            tx,
+            rx,
+            buffer: Mutex::new(String::new()),
         }
     }
 
     #[allow(dead_code)]
     pub fn get_output(&self) -> String {
-        self.buffer.lock().unwrap().clone()
+        let mut buf = self.buffer.lock().unwrap();
+        for line in self.rx.try_iter() {
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+        buf.clone()
     }
 }
 
@@ -42,23 +494,640 @@ impl Default for BufferWriter {
 
 impl OutputWriter for BufferWriter {
     fn write_line(&self, content: &str) {
-        let mut buf = self.buffer.lock().unwrap();
-        buf.push_str(content);
-        buf.push('\n');
+        // the receiver lives as long as the writer, so sending cannot fail
+        let _ = self.tx.send(content.to_string());
+    }
+}
+
+/// Writer for any [`Write`], e.g. a file, socket or `Vec<u8>`
+///
+/// Lines are written under a lock, so unlike [`BufferWriter`] concurrent writers contend on it;
+/// wrap the sink in a `BufWriter` to avoid a write call per line.
+#[allow(dead_code)]
+pub struct IoWriter<W: Write + Send> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send> IoWriter<W> {
+    #[allow(dead_code)]
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Take back the sink, e.g. to read the bytes written to a `Vec<u8>`
+    #[allow(dead_code)]
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap()
     }
 }
 
+impl<W: Write + Send> OutputWriter for IoWriter<W> {
+    fn write_line(&self, content: &str) {
+        // ignore write errors, as for `StdoutWriter`
+        let _ = writeln!(self.out.lock().unwrap(), "{}", content);
+    }
+
+    fn flush(&self) {
+        let _ = self.out.lock().unwrap().flush();
+    }
+}
+
+/// Writer for a file, e.g. from `--output-file`
+///
+/// Unlike [`IoWriter`], a failed write is reported once on stderr, after which nothing more is
+/// written to the file, so the other writers of a [`TeeWriter`] carry on.
+pub struct FileWriter {
+    path: PathBuf,
+    /// `None` once a write has failed
+    out: Mutex<Option<BufWriter<File>>>,
+}
+
+impl FileWriter {
+    /// Create the file, replacing any file already at `path`
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            out: Mutex::new(Some(BufWriter::new(file))),
+        })
+    }
+
+    fn write_with(&self, write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>) {
+        let mut out = self.out.lock().unwrap();
+        if let Some(Err(e)) = out.as_mut().map(write) {
+            eprintln!(
+                "could not write to {}: {e}, the rest of the output is not saved",
+                self.path.display()
+            );
+            *out = None;
+        }
+    }
+}
+
+impl OutputWriter for FileWriter {
+    fn write_line(&self, content: &str) {
+        self.write_with(|out| writeln!(out, "{}", content));
+    }
+
+    fn flush(&self) {
+        self.write_with(|out| out.flush());
+    }
+}
+
+/// Writer that sends every line to each of several writers, e.g. to show the tree on screen and
+/// save it to a file in one run
+///
+/// Writers are independent, so one that fails to write does not stop the others.
+pub struct TeeWriter {
+    writers: Vec<Box<dyn OutputWriter>>,
+}
+
+impl TeeWriter {
+    pub fn new(writers: Vec<Box<dyn OutputWriter>>) -> Self {
+        Self { writers }
+    }
+}
+
+impl OutputWriter for TeeWriter {
+    fn write_line(&self, content: &str) {
+        for writer in &self.writers {
+            writer.write_line(content);
+        }
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        for writer in &self.writers {
+            writer.write_node(indent, content);
+        }
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        for writer in &self.writers {
+            writer.write_resource(indent, content, arn);
+        }
+    }
+
+    fn write_separator(&self) {
+        for writer in &self.writers {
+            writer.write_separator();
+        }
+    }
+
+    fn flush(&self) {
+        for writer in &self.writers {
+            writer.flush();
+        }
+    }
+}
+
+/// Mermaid flowchart writer, for embedding the tree in Markdown
+///
+/// Nodes are collected as they are written and the diagram is written to the inner writer by
+/// [`MermaidWriter::finish`], since the `graph TD` header has to come first.
+pub struct MermaidWriter<W: OutputWriter> {
+    inner: W,
+    state: Mutex<MermaidState>,
+}
+
+#[derive(Default)]
+struct MermaidState {
+    lines: Vec<String>,
+    next_id: usize,
+    /// (indent, node id) of the current node and each of its ancestors
+    parents: Vec<(usize, String)>,
+}
+
+impl<W: OutputWriter> MermaidWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(MermaidState::default()),
+        }
+    }
+
+    /// Write the diagram collected so far to the inner writer
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.inner.write_line("graph TD");
+        for line in state.lines.drain(..) {
+            self.inner.write_line(&format!("    {}", line));
+        }
+        state.parents.clear();
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for MermaidWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.write_node(0, content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        let id = format!("n{}", state.next_id);
+        state.next_id += 1;
+        let node = format!("{}[\"{}\"]", id, mermaid_label(content));
+
+        while state
+            .parents
+            .last()
+            .is_some_and(|(parent_indent, _)| *parent_indent >= indent)
+        {
+            state.parents.pop();
+        }
+        let line = match state.parents.last() {
+            Some((_, parent)) => format!("{}-->{}", parent, node),
+            None => node,
+        };
+        state.lines.push(line);
+        state.parents.push((indent, id));
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.finish();
+        self.inner.flush();
+    }
+}
+
+/// Markdown writer, rendering the tree as a nested bullet list
+pub struct MarkdownWriter<W: OutputWriter> {
+    inner: W,
+}
+
+impl<W: OutputWriter> MarkdownWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for MarkdownWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.write_node(0, content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        // each level of the tree is nested two more spaces, as Markdown expects
+        let prefix = "  ".repeat(indent / INDENT_WIDTH);
+        self.inner
+            .write_line(&format!("{}- {}", prefix, markdown_escape(content)));
+    }
+
+    // a blank line ends one list and starts the next, rather than an empty bullet
+    fn write_separator(&self) {
+        self.inner.write_line("");
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Backslash-escape characters that Markdown would otherwise treat as formatting
+fn markdown_escape(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    for c in content.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// JSON writer, emitting the tree as nested [`Node`]s
+///
+/// Like [`MermaidWriter`] the tree is only written to the inner writer by [`JsonWriter::finish`].
+/// The output can be rendered again later with `--from-file`.
+pub struct JsonWriter<W: OutputWriter> {
+    inner: W,
+    tree: Mutex<TreeBuilder>,
+    pretty: bool,
+}
+
+impl<W: OutputWriter> JsonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            tree: Mutex::new(TreeBuilder::default()),
+            pretty: false,
+        }
+    }
+
+    /// Indent the JSON for reading rather than writing it on a single line
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Write the tree collected so far to the inner writer
+    pub fn finish(&self) {
+        let roots: Vec<Node> = self.tree.lock().unwrap().finish();
+        // panic safety: nodes only contain strings, which can always be serialized
+        let json = if self.pretty {
+            serde_json::to_string_pretty(&roots).unwrap()
+        } else {
+            serde_json::to_string(&roots).unwrap()
+        };
+        self.inner.write_line(&json);
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for JsonWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.write_node(0, content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.write_resource(indent, content, None);
+    }
+
+    fn write_resource(&self, indent: usize, content: &str, arn: Option<&str>) {
+        self.tree.lock().unwrap().push(indent, content, arn);
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.finish();
+        self.inner.flush();
+    }
+}
+
+/// HTML writer, emitting a self-contained page where each subtree can be collapsed
+///
+/// Whether a node has children is only known once the next node is written, so like
+/// [`JsonWriter`] the tree is collected and written to the inner writer by [`HtmlWriter::finish`].
+pub struct HtmlWriter<W: OutputWriter> {
+    inner: W,
+    tree: Mutex<TreeBuilder>,
+}
+
+impl<W: OutputWriter> HtmlWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            tree: Mutex::new(TreeBuilder::default()),
+        }
+    }
+
+    /// Write the page for the tree collected so far to the inner writer
+    pub fn finish(&self) {
+        let roots: Vec<Node> = self.tree.lock().unwrap().finish();
+        self.inner.write_line("<!DOCTYPE html>");
+        self.inner.write_line("<html>");
+        self.inner.write_line(
+            "<head><meta charset=\"utf-8\"><title>lbtree</title>\
+             <style>body { font-family: monospace; } ul { list-style: none; }</style></head>",
+        );
+        self.inner.write_line("<body>");
+        self.inner.write_line("<ul>");
+        for root in &roots {
+            self.write_html_node(root, 1);
+        }
+        self.inner.write_line("</ul>");
+        self.inner.write_line("</body>");
+        self.inner.write_line("</html>");
+    }
+
+    fn write_html_node(&self, node: &Node, depth: usize) {
+        let pad = "  ".repeat(depth);
+        let content = html_escape(&node.content);
+        if node.children.is_empty() {
+            self.inner.write_line(&format!("{pad}<li>{content}</li>"));
+            return;
+        }
+
+        self.inner.write_line(&format!(
+            "{pad}<li><details open><summary>{content}</summary>"
+        ));
+        self.inner.write_line(&format!("{pad}<ul>"));
+        for child in &node.children {
+            self.write_html_node(child, depth + 1);
+        }
+        self.inner.write_line(&format!("{pad}</ul>"));
+        self.inner.write_line(&format!("{pad}</details></li>"));
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for HtmlWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.write_node(0, content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        self.tree.lock().unwrap().push(indent, content, None);
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.finish();
+        self.inner.flush();
+    }
+}
+
+/// Escape characters that HTML would otherwise treat as markup
+fn html_escape(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// JSON lines writer, emitting each node as a standalone JSON object
+///
+/// Each object is flushed as soon as it is written, so a consumer sees every tree as it is
+/// rendered rather than only at the end of the run. The nodes of one tree are only known once
+/// the whole tree has been fetched.
+pub struct JsonLinesWriter<W: OutputWriter> {
+    inner: W,
+}
+
+impl<W: OutputWriter> JsonLinesWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for JsonLinesWriter<W> {
+    fn write_line(&self, content: &str) {
+        self.write_node(0, content);
+    }
+
+    fn write_node(&self, indent: usize, content: &str) {
+        let line = serde_json::json!({
+            "depth": indent / INDENT_WIDTH,
+            "text": content,
+        });
+        self.inner.write_line(&line.to_string());
+        self.inner.flush();
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that only outputs the ARN or id of each resource, one per line, for scripting
+pub struct ArnWriter<W: OutputWriter> {
+    inner: W,
+}
+
+impl<W: OutputWriter> ArnWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for ArnWriter<W> {
+    fn write_line(&self, _content: &str) {}
+
+    fn write_node(&self, _indent: usize, _content: &str) {}
+
+    fn write_resource(&self, _indent: usize, _content: &str, arn: Option<&str>) {
+        if let Some(arn) = arn {
+            self.inner.write_line(arn);
+        }
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Writer that outputs the AWS CLI command to describe each resource, one per line
+///
+/// The command is chosen from the ARN of the resource. REST APIs and their resources only have
+/// ids, so they are recognised by their position in the tree instead.
+pub struct CommandWriter<W: OutputWriter> {
+    inner: W,
+    state: Mutex<CommandState>,
+}
+
+#[derive(Default)]
+struct CommandState {
+    /// ARN of the most recent ECS cluster, needed to describe its services and tasks
+    cluster: Option<String>,
+    /// id of the most recent REST API, needed to describe its resources
+    rest_api: Option<String>,
+}
+
+impl<W: OutputWriter> CommandWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(CommandState::default()),
+        }
+    }
+
+    /// Access the inner writer, e.g. to read back a `BufferWriter`
+    #[allow(dead_code)]
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    fn commands(&self, indent: usize, arn: &str) -> Vec<String> {
+        let mut state = self.state.lock().unwrap();
+
+        if !arn.starts_with("arn:") {
+            return match (indent, state.rest_api.clone()) {
+                (0, _) => {
+                    state.rest_api = Some(arn.to_string());
+                    vec![format!("aws apigateway get-rest-api --rest-api-id {arn}")]
+                }
+                (INDENT_WIDTH, Some(api)) => vec![format!(
+                    "aws apigateway get-resource --rest-api-id {api} --resource-id {arn}"
+                )],
+                // targets are covered by describe-target-health on their target group
+                _ => Vec::new(),
+            };
+        }
+        if indent == 0 {
+            state.rest_api = None;
+        }
+
+        // the resource part of an ARN, e.g. `loadbalancer/app/my-lb/50dc6c495c0c9188`
+        let resource = arn.splitn(6, ':').nth(5).unwrap_or_default();
+        let kind = resource.split('/').next().unwrap_or_default();
+        let cluster = state.cluster.clone().unwrap_or_default();
+        match kind {
+            "loadbalancer" => vec![format!(
+                "aws elbv2 describe-load-balancers --load-balancer-arns {arn}"
+            )],
+            "listener" => vec![format!(
+                "aws elbv2 describe-listeners --listener-arns {arn}"
+            )],
+            "listener-rule" => vec![format!("aws elbv2 describe-rules --rule-arns {arn}")],
+            "targetgroup" => vec![
+                format!("aws elbv2 describe-target-groups --target-group-arns {arn}"),
+                format!("aws elbv2 describe-target-health --target-group-arn {arn}"),
+            ],
+            "cluster" => {
+                state.cluster = Some(arn.to_string());
+                vec![format!("aws ecs describe-clusters --clusters {arn}")]
+            }
+            "service" => vec![format!(
+                "aws ecs describe-services --cluster {cluster} --services {arn}"
+            )],
+            "task" => vec![format!(
+                "aws ecs describe-tasks --cluster {cluster} --tasks {arn}"
+            )],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<W: OutputWriter> OutputWriter for CommandWriter<W> {
+    fn write_line(&self, _content: &str) {}
+
+    fn write_node(&self, _indent: usize, _content: &str) {}
+
+    fn write_resource(&self, indent: usize, _content: &str, arn: Option<&str>) {
+        let Some(arn) = arn else {
+            return;
+        };
+        for command in self.commands(indent, arn) {
+            self.inner.write_line(&command);
+        }
+    }
+
+    fn write_separator(&self) {}
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Escape characters that would end a quoted Mermaid label early
+fn mermaid_label(content: &str) -> String {
+    content.replace('"', "#quot;")
+}
+
 /// Trait for presenting AWS resources in a tree structure
 pub trait Present: std::fmt::Debug + Send + Sync + 'static {
     /// Get the string representation of this resource
     fn content(&self) -> String;
 
-    /// Get the indentation level for this resource
-    fn indent(&self) -> usize;
+    /// Get the ARN of this resource, or its id if it does not have an ARN
+    fn arn(&self) -> Option<String> {
+        None
+    }
+
+    /// Present this resource using the provided output writer, at the given depth in the tree
+    #[allow(dead_code)]
+    fn present(&self, writer: &dyn OutputWriter, depth: usize) {
+        writer.write_resource(depth * INDENT_WIDTH, &self.content(), self.arn().as_deref());
+    }
+}
+
+/// Placeholder for a branch of the tree the credentials are not allowed to fetch, e.g. the rules
+/// of a listener under a role without `elasticloadbalancing:DescribeRules`
+#[derive(Debug, Clone, Copy)]
+pub struct AccessDenied;
 
-    /// Present this resource using the provided output writer
-    fn present(&self, writer: &dyn OutputWriter) {
-        let prefix = " ".repeat(self.indent()) + "-> ";
-        writer.write_line(&format!("{}{}", prefix, self.content()));
+impl Present for AccessDenied {
+    fn content(&self) -> String {
+        "<access denied>".to_string()
     }
 }
+
+/// A resource waiting to be rendered, with its depth in the tree where the root is at depth 0
+///
+/// The depth is carried with each resource rather than fixed per type, so the same type can
+/// appear at different levels, e.g. nested API Gateway resources.
+pub type Queued = (usize, Box<dyn Present>);
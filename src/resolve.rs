@@ -0,0 +1,125 @@
+use color_eyre::eyre;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Cache of looked up values by id, e.g. names by resource id, shared for the whole run
+///
+/// Clones share the same entries. The lock is not held while fetching, so two concurrent
+/// lookups of a missing key may both call the API; the later result wins.
+#[derive(Debug, Clone)]
+pub struct ResolverCache<V = String> {
+    entries: Arc<Mutex<HashMap<String, V>>>,
+}
+
+impl<V> Default for ResolverCache<V> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<V: Clone> ResolverCache<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the cached value for `key`, or run `fetch` and remember its result
+    ///
+    /// Errors are returned without being cached, so the next lookup tries again.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &str, fetch: F) -> eyre::Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = eyre::Result<V>>,
+    {
+        if let Some(value) = self.entries.lock().unwrap().get(key) {
+            return Ok(value.clone());
+        }
+        let value = fetch().await?;
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+}
+
+/// Pick the single resource matching a name or partial ARN given on the command line
+///
+/// `matches` holds the ARN (or id) of every resource that matched `input`; an error is returned
+/// if there are none, or more than one so the user has to be more specific.
+pub fn unique_match(kind: &str, input: &str, mut matches: Vec<String>) -> eyre::Result<String> {
+    match matches.len() {
+        0 => eyre::bail!("No {kind} matches {input:?}"),
+        1 => Ok(matches.remove(0)),
+        n => eyre::bail!(
+            "{input:?} matches {n} {kind}s, use the full ARN or a longer name:\n  {}",
+            matches.join("\n  ")
+        ),
+    }
+}
+
+/// Whether an ARN ends with `input` as a whole path component, e.g. `my-lb/50dc6c495c0c9188` or
+/// `50dc6c495c0c9188` for `arn:...:loadbalancer/app/my-lb/50dc6c495c0c9188`
+pub fn arn_has_suffix(arn: &str, input: &str) -> bool {
+    arn == input
+        || arn
+            .strip_suffix(input)
+            .is_some_and(|prefix| prefix.ends_with('/') || prefix.ends_with(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arn_has_suffix() {
+        let arn = "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/my-lb/50dc";
+        assert!(arn_has_suffix(arn, "50dc"));
+        assert!(arn_has_suffix(arn, "my-lb/50dc"));
+        assert!(arn_has_suffix(arn, arn));
+        assert!(!arn_has_suffix(arn, "dc"));
+    }
+
+    #[tokio::test]
+    async fn test_resolver_cache_fetches_once() {
+        let cache: ResolverCache = ResolverCache::new();
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let fetch = || async {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("my-link".to_string())
+        };
+        assert_eq!(cache.get_or_fetch("vl-1", fetch).await.unwrap(), "my-link");
+        assert_eq!(cache.get_or_fetch("vl-1", fetch).await.unwrap(), "my-link");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // failures are not remembered
+        let shared = cache.clone();
+        assert!(
+            shared
+                .get_or_fetch("vl-2", || async { eyre::bail!("throttled") })
+                .await
+                .is_err()
+        );
+        assert_eq!(cache.get_or_fetch("vl-2", fetch).await.unwrap(), "my-link");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_unique_match() {
+        assert_eq!(
+            unique_match("cluster", "prod", vec!["arn:prod".to_string()]).unwrap(),
+            "arn:prod"
+        );
+        assert!(unique_match("cluster", "prod", Vec::new()).is_err());
+
+        let err = unique_match(
+            "cluster",
+            "prod",
+            vec!["arn:a/prod".to_string(), "arn:b/prod".to_string()],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("matches 2 clusters"));
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::present::{OutputWriter, Present, Queued};
+
+/// Number of spaces each level of the tree is indented by
+pub const INDENT_WIDTH: usize = 2;
+
+/// A rendered resource and its children
+///
+/// This is the format written by `--output json` and read back by `--from-file`, so a captured
+/// tree is rendered exactly as if it had been fetched live.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    pub content: String,
+    /// ARN, or id for resources without one, e.g. for `--arns-only`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arn: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            arn: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_arn(mut self, arn: Option<String>) -> Self {
+        self.arn = arn;
+        self
+    }
+
+    /// Render this node and its children, with this node at the root of the tree
+    pub fn render(&self, writer: &dyn OutputWriter) {
+        self.render_at(writer, 0);
+    }
+
+    fn render_at(&self, writer: &dyn OutputWriter, depth: usize) {
+        writer.write_resource(depth * INDENT_WIDTH, &self.content, self.arn.as_deref());
+        for child in &self.children {
+            child.render_at(writer, depth + 1);
+        }
+    }
+}
+
+/// Builds a forest of nodes from indented lines written in tree order
+#[derive(Debug, Default)]
+pub struct TreeBuilder {
+    roots: Vec<Node>,
+    /// indent of the most recent node and each of its ancestors
+    path: Vec<usize>,
+}
+
+impl TreeBuilder {
+    /// Add a node as a child of the most recent node with a smaller indent
+    pub fn push(&mut self, indent: usize, content: &str, arn: Option<&str>) {
+        while self.path.last().is_some_and(|&parent| parent >= indent) {
+            self.path.pop();
+        }
+
+        let mut siblings = &mut self.roots;
+        for _ in 0..self.path.len() {
+            // panic safety: every entry in `path` corresponds to the last node at that depth
+            siblings = &mut siblings.last_mut().unwrap().children;
+        }
+        siblings.push(Node::new(content).with_arn(arn.map(|arn| arn.to_string())));
+        self.path.push(indent);
+    }
+
+    /// Take the nodes collected so far, leaving the builder empty
+    pub fn finish(&mut self) -> Vec<Node> {
+        self.path.clear();
+        std::mem::take(&mut self.roots)
+    }
+}
+
+/// Build the tree of a resource from its descendants, listed in tree order with their depth
+pub fn build_tree(root: &dyn Present, descendants: &[Queued]) -> Node {
+    let mut builder = TreeBuilder::default();
+    builder.push(0, &root.content(), root.arn().as_deref());
+    for (depth, presenter) in descendants {
+        builder.push(
+            depth * INDENT_WIDTH,
+            &presenter.content(),
+            presenter.arn().as_deref(),
+        );
+    }
+    // panic safety: the root was pushed first so there is always at least one node
+    builder.finish().swap_remove(0)
+}
@@ -63,6 +63,7 @@ impl AlbTestFixture {
             .await?;
         let vpc_id = vpc.vpc().unwrap().vpc_id().unwrap().to_string();
         self.vpc_id = Some(vpc_id.clone());
+        self.insta_settings.add_filter(&vpc_id, "[vpc-id]");
 
         // 2. Create subnets in different AZs (required for ALB)
         let subnet1 = self
@@ -74,6 +75,7 @@ impl AlbTestFixture {
             .send()
             .await?;
         let subnet1_id = subnet1.subnet().unwrap().subnet_id().unwrap().to_string();
+        self.insta_settings.add_filter(&subnet1_id, "[subnet-1-id]");
         self.subnet_ids.push(subnet1_id);
 
         let subnet2 = self
@@ -85,6 +87,7 @@ impl AlbTestFixture {
             .send()
             .await?;
         let subnet2_id = subnet2.subnet().unwrap().subnet_id().unwrap().to_string();
+        self.insta_settings.add_filter(&subnet2_id, "[subnet-2-id]");
         self.subnet_ids.push(subnet2_id);
 
         // 3. Create security group
@@ -99,6 +102,7 @@ impl AlbTestFixture {
             .await?;
         let sg_id = sg.group_id().unwrap().to_string();
         self.security_group_id = Some(sg_id.clone());
+        self.insta_settings.add_filter(&sg_id, "[sg-id]");
 
         // 4. Create target group
         let tg_name = format!("test-tg-{}", short_id);
@@ -210,7 +214,13 @@ impl AlbTestFixture {
 
     async fn run_display(&self) -> color_eyre::Result<String> {
         let writer = BufferWriter::new();
-        lbtree::alb::display_alb(&self.config, self.load_balancer_arn.clone(), &writer).await?;
+        lbtree::alb::display_alb(
+            &self.config,
+            self.load_balancer_arn.clone(),
+            Default::default(),
+            &writer,
+        )
+        .await?;
         Ok(writer.get_output())
     }
 
@@ -0,0 +1,93 @@
+use aws_sdk_elasticloadbalancingv2::types::{
+    Action, ActionTypeEnum, HostHeaderConditionConfig, Rule, RuleCondition,
+};
+use lbtree::alb::{TestRequest, match_request};
+
+fn forward() -> Action {
+    Action::builder().r#type(ActionTypeEnum::Forward).build()
+}
+
+fn fixed_response() -> Action {
+    Action::builder()
+        .r#type(ActionTypeEnum::FixedResponse)
+        .build()
+}
+
+fn rules() -> Vec<Rule> {
+    vec![
+        Rule::builder()
+            .priority("default")
+            .is_default(true)
+            .actions(forward())
+            .build(),
+        Rule::builder()
+            .priority("20")
+            .conditions(
+                RuleCondition::builder()
+                    .field("path-pattern")
+                    .values("/api/*")
+                    .build(),
+            )
+            .actions(forward())
+            .build(),
+        Rule::builder()
+            .priority("10")
+            .conditions(
+                RuleCondition::builder()
+                    .field("host-header")
+                    .host_header_config(
+                        HostHeaderConditionConfig::builder()
+                            .values("*.example.com")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .conditions(
+                RuleCondition::builder()
+                    .field("path-pattern")
+                    .values("/admin*")
+                    .build(),
+            )
+            .actions(fixed_response())
+            .build(),
+    ]
+}
+
+#[test]
+fn test_match_request_uses_priority_order() {
+    let rules = rules();
+
+    let (rule, action) = match_request(&rules, "api.EXAMPLE.com", "/admin/users", "GET").unwrap();
+    assert_eq!(rule.priority(), Some("10"));
+    assert_eq!(action.r#type(), Some(&ActionTypeEnum::FixedResponse));
+
+    let (rule, _) = match_request(&rules, "other.net", "/api/v1", "GET").unwrap();
+    assert_eq!(rule.priority(), Some("20"));
+}
+
+#[test]
+fn test_match_request_falls_back_to_default_rule() {
+    let rules = rules();
+
+    let (rule, _) = match_request(&rules, "other.net", "/admin", "POST").unwrap();
+    assert_eq!(rule.priority(), Some("default"));
+}
+
+#[test]
+fn test_parse_test_request() {
+    let req =
+        TestRequest::parse("https://user@api.example.com:8443/admin?debug=1", "post").unwrap();
+    assert_eq!(
+        req,
+        TestRequest {
+            host: "api.example.com".to_string(),
+            path: "/admin".to_string(),
+            method: "POST".to_string(),
+        }
+    );
+
+    let req = TestRequest::parse("api.example.com", "GET").unwrap();
+    assert_eq!(req.path, "/");
+
+    assert!(TestRequest::parse("https:///admin", "GET").is_err());
+}
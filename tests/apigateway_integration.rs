@@ -44,7 +44,7 @@ impl ApiGatewayTestFixture {
             .await?;
         self.api_id = api.id().map(|s| s.to_string());
         let api_id = self.api_id.as_ref().unwrap();
-        self.insta_settings.add_filter(&api_id, "[api-id]");
+        self.insta_settings.add_filter(api_id, "[api-id]");
 
         // Get the root resource
         let resources = self
@@ -138,6 +138,49 @@ impl ApiGatewayTestFixture {
                 .await?;
         }
 
+        // Create a greedy proxy resource /{proxy+} forwarding every method and path
+        let proxy_resource = self
+            .client
+            .create_resource()
+            .rest_api_id(api_id)
+            .parent_id(root_id)
+            .path_part("{proxy+}")
+            .send()
+            .await?;
+        if let Some(id) = proxy_resource.id().map(|s| s.to_string()) {
+            self.insta_settings.add_filter(&id, "[proxy-resource-id]");
+            self.resource_ids.push(id.clone());
+
+            // Create ANY method on /{proxy+}
+            let _ = self
+                .client
+                .put_method()
+                .rest_api_id(api_id)
+                .resource_id(&id)
+                .http_method("ANY")
+                .authorization_type("NONE")
+                .request_parameters("method.request.path.proxy", true)
+                .send()
+                .await?;
+
+            // Create proxy integration for ANY /{proxy+}, which is fetched with the literal ANY
+            let _ = self
+                .client
+                .put_integration()
+                .rest_api_id(api_id)
+                .resource_id(&id)
+                .http_method("ANY")
+                .integration_http_method("ANY")
+                .r#type(aws_sdk_apigateway::types::IntegrationType::HttpProxy)
+                .uri("http://example.com/{proxy}")
+                .request_parameters(
+                    "integration.request.path.proxy",
+                    "method.request.path.proxy",
+                )
+                .send()
+                .await?;
+        }
+
         Ok(())
     }
 
@@ -182,6 +225,9 @@ async fn test_apigateway_display_snapshot() {
 
     // Now check the result
     let output = result.expect("error with test");
+    // the resource id is only filtered in the snapshot
+    assert!(output.contains(") (greedy proxy)\n"));
+    assert!(output.contains("ANY auth=NONE (all methods)"));
 
     // Use insta for snapshot testing
     fixture.insta_settings.bind(|| {
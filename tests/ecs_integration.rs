@@ -4,7 +4,8 @@ use aws_sdk_ec2::Client as Ec2Client;
 use aws_sdk_ecs::Client as EcsClient;
 use aws_sdk_ecs::client::Waiters;
 use aws_sdk_ecs::types::{
-    AssignPublicIp, AwsVpcConfiguration, Compatibility, ContainerDefinition, KeyValuePair,
+    AssignPublicIp, AwsVpcConfiguration, Compatibility, ContainerCondition, ContainerDefinition,
+    ContainerDependency, HealthCheck, KeyValuePair, LogConfiguration, LogDriver,
     NetworkConfiguration, NetworkMode,
 };
 use lbtree::present::BufferWriter;
@@ -133,6 +134,14 @@ impl EcsTestFixture {
                     .cpu(128)
                     .memory(256)
                     .environment(KeyValuePair::builder().name("ENV").value("test").build())
+                    .log_configuration(
+                        LogConfiguration::builder()
+                            .log_driver(LogDriver::Awslogs)
+                            .options("awslogs-group", "/ecs/lbtree-test")
+                            .options("awslogs-region", "us-east-1")
+                            .options("awslogs-stream-prefix", "app")
+                            .build()?,
+                    )
                     .build(),
             )
             .container_definitions(
@@ -145,6 +154,21 @@ impl EcsTestFixture {
                     .command("sh")
                     .command("-c")
                     .command("while true; do echo done; sleep 1; done")
+                    .health_check(
+                        HealthCheck::builder()
+                            .command("CMD-SHELL")
+                            .command("exit 0")
+                            .interval(10)
+                            .timeout(5)
+                            .retries(3)
+                            .build()?,
+                    )
+                    .depends_on(
+                        ContainerDependency::builder()
+                            .container_name("app")
+                            .condition(ContainerCondition::Start)
+                            .build()?,
+                    )
                     .build(),
             )
             .send()
@@ -203,6 +227,9 @@ impl EcsTestFixture {
             r"[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}",
             "[task-id]",
         );
+        // running tasks show how long they have been up, which changes between runs
+        self.insta_settings
+            .add_filter(r" age=[0-9dhms]+", " age=[age]");
 
         // Wait for service to stabilize (tasks running)
         self.ecs_client
@@ -221,6 +248,7 @@ impl EcsTestFixture {
             &self.config,
             self.cluster_arn.clone(),
             self.service_arn.clone(),
+            Default::default(),
             &writer,
         )
         .await?;
@@ -324,6 +352,18 @@ async fn test_ecs_display_snapshot() {
 
     // Now check the result
     let output = result.expect("error with test");
+    // the fixture service is not registered with a load balancer, so has no grace period
+    assert!(output.contains("load-balancers=0"));
+    assert!(!output.contains("health-check-grace="));
+    // the task's budget split between the two containers of the fixture
+    assert!(
+        output.contains(r#"Container "app" repo=nginx tag=latest status=RUNNING cpu=128 mem=256"#)
+    );
+    assert!(
+        output.contains(
+            r#"Container "sidecar" repo=busybox tag=latest status=RUNNING cpu=64 mem=128"#
+        )
+    );
 
     // Use insta for snapshot testing
     fixture.insta_settings.bind(|| {
@@ -0,0 +1,381 @@
+use lbtree::present::{
+    ArnWriter, BufferWriter, CommandWriter, FileWriter, HtmlWriter, IndentWriter, IoWriter,
+    JsonLinesWriter, JsonWriter, MarkdownWriter, MaxDepthWriter, MaxItemsWriter, MermaidWriter,
+    OutputWriter, PlainWriter, PrefixWriter, ShowArnsWriter, TeeWriter,
+};
+use lbtree::tree::Node;
+
+#[test]
+fn test_mermaid_writer_emits_one_edge_per_child() {
+    let writer = MermaidWriter::new(BufferWriter::new());
+    writer.write_node(0, "Load balancer (lb.example.com)");
+    writer.write_node(2, "Listener protocol=HTTP port=80");
+    writer.write_node(4, "Rule priority=default is-default=true");
+    writer.write_node(6, "Action (forward)");
+    writer.write_node(2, "Target group \"web\" protocol=HTTP port=80");
+    writer.finish();
+
+    let output = writer.inner().get_output();
+    assert!(output.starts_with("graph TD\n"));
+    assert_eq!(output.matches("-->").count(), 4);
+    assert!(output.contains("n0-->n4[\"Target group #quot;web#quot; protocol=HTTP port=80\"]"));
+}
+
+#[test]
+fn test_json_writer_round_trips_through_node_tree() {
+    let lines = [
+        (0, "Load balancer (lb.example.com)"),
+        (2, "Listener protocol=HTTP port=80"),
+        (4, "Rule priority=default is-default=true"),
+        (6, "Action (forward)"),
+        (2, "Target group \"web\" protocol=HTTP port=80"),
+        (4, "Target id=10.0.0.1 port=80"),
+    ];
+
+    let live = BufferWriter::new();
+    let json = JsonWriter::new(BufferWriter::new());
+    for (indent, content) in lines {
+        live.write_node(indent, content);
+        json.write_node(indent, content);
+    }
+    json.finish();
+
+    let roots: Vec<Node> = serde_json::from_str(&json.inner().get_output()).unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].children.len(), 2);
+
+    let replayed = BufferWriter::new();
+    for root in &roots {
+        root.render(&replayed);
+    }
+    assert_eq!(replayed.get_output(), live.get_output());
+}
+
+#[test]
+fn test_json_writer_nests_children_by_indentation() {
+    let json = JsonWriter::new(BufferWriter::new());
+    json.write_node(0, "Load balancer");
+    json.write_node(2, "Listener");
+    json.write_node(4, "Rule");
+    json.write_node(2, "Target group");
+    json.finish();
+
+    // the shape `jq '.[].children[].content'` relies on
+    let roots: serde_json::Value = serde_json::from_str(&json.inner().get_output()).unwrap();
+    assert_eq!(roots[0]["content"], "Load balancer");
+    assert_eq!(roots[0]["children"][0]["content"], "Listener");
+    assert_eq!(roots[0]["children"][0]["children"][0]["content"], "Rule");
+    assert_eq!(roots[0]["children"][1]["content"], "Target group");
+    assert!(roots[0]["children"][1].get("children").is_none());
+    assert!(roots[0].get("depth").is_none());
+}
+
+#[test]
+fn test_markdown_writer_nests_and_escapes() {
+    let writer = MarkdownWriter::new(BufferWriter::new());
+    writer.write_node(0, "Load balancer (lb.example.com)");
+    writer.write_node(2, "Listener protocol=HTTP port=80");
+    writer.write_node(4, "Rule priority=default is-default=true");
+    writer.write_node(2, "Target group \"my_tg\" protocol=HTTP port=80");
+
+    insta::assert_snapshot!(writer.inner().get_output(), @r#"
+    - Load balancer (lb.example.com)
+      - Listener protocol=HTTP port=80
+        - Rule priority=default is-default=true
+      - Target group "my\_tg" protocol=HTTP port=80
+    "#);
+}
+
+#[test]
+fn test_html_writer_collapses_each_subtree() {
+    let writer = HtmlWriter::new(BufferWriter::new());
+    writer.write_node(0, "Load balancer (lb.example.com)");
+    writer.write_node(2, "Listener protocol=HTTP port=80");
+    writer.write_node(4, "Rule priority=default is-default=true");
+    writer.write_node(6, "Action (forward)");
+    writer.write_node(2, "Target group \"<script>\" protocol=HTTP port=80");
+    writer.finish();
+
+    let output = writer.inner().get_output();
+    assert!(output.starts_with("<!DOCTYPE html>\n"));
+    // the load balancer, listener and rule have children
+    assert_eq!(output.matches("<details").count(), 3);
+    for tag in ["html", "body", "ul", "li", "details", "summary"] {
+        assert_eq!(
+            output.matches(&format!("<{tag}>")).count()
+                + output.matches(&format!("<{tag} ")).count(),
+            output.matches(&format!("</{tag}>")).count(),
+            "unbalanced <{tag}>"
+        );
+    }
+    assert!(output.contains("Target group &quot;&lt;script&gt;&quot; protocol=HTTP port=80"));
+}
+
+#[test]
+fn test_json_lines_writer_emits_standalone_objects() {
+    let writer = JsonLinesWriter::new(BufferWriter::new());
+    writer.write_node(0, "Load balancer (lb.example.com)");
+    writer.write_node(2, "Listener protocol=HTTP port=80");
+    writer.write_node(4, "Rule \"quoted\"");
+
+    let output = writer.inner().get_output();
+    let lines: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1]["depth"], 1);
+    assert_eq!(lines[2]["text"], "Rule \"quoted\"");
+}
+
+#[test]
+fn test_arn_writer_only_prints_arns() {
+    let mut root = Node::new("Load balancer (lb.example.com)")
+        .with_arn(Some("arn:aws:elasticloadbalancing:lb".to_string()));
+    root.children.push(
+        Node::new("Listener protocol=HTTP port=80")
+            .with_arn(Some("arn:aws:elasticloadbalancing:listener".to_string())),
+    );
+    root.children[0]
+        .children
+        .push(Node::new("Action (forward)"));
+
+    let writer = ArnWriter::new(BufferWriter::new());
+    root.render(&writer);
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "arn:aws:elasticloadbalancing:lb\narn:aws:elasticloadbalancing:listener\n"
+    );
+}
+
+#[test]
+fn test_command_writer_describes_each_resource() {
+    let lb_arn = "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/lb/1";
+    let tg_arn = "arn:aws:elasticloadbalancing:us-east-1:000000000000:targetgroup/tg/2";
+    let mut root = Node::new("Load balancer").with_arn(Some(lb_arn.to_string()));
+    let mut target_group = Node::new("Target group").with_arn(Some(tg_arn.to_string()));
+    target_group
+        .children
+        .push(Node::new("Target").with_arn(Some("10.0.0.1".to_string())));
+    root.children.push(target_group);
+
+    let writer = CommandWriter::new(BufferWriter::new());
+    root.render(&writer);
+
+    insta::assert_snapshot!(writer.inner().get_output(), @r"
+    aws elbv2 describe-load-balancers --load-balancer-arns arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/lb/1
+    aws elbv2 describe-target-groups --target-group-arns arn:aws:elasticloadbalancing:us-east-1:000000000000:targetgroup/tg/2
+    aws elbv2 describe-target-health --target-group-arn arn:aws:elasticloadbalancing:us-east-1:000000000000:targetgroup/tg/2
+    ");
+}
+
+#[test]
+fn test_json_writer_pretty() {
+    let compact = JsonWriter::new(BufferWriter::new());
+    let pretty = JsonWriter::new(BufferWriter::new()).pretty(true);
+    for writer in [&compact, &pretty] {
+        writer.write_node(0, "Load balancer");
+        writer.write_node(2, "Listener");
+        writer.finish();
+    }
+
+    assert_eq!(compact.inner().get_output().lines().count(), 1);
+    assert!(pretty.inner().get_output().lines().count() > 1);
+    let compact: Vec<Node> = serde_json::from_str(&compact.inner().get_output()).unwrap();
+    let pretty: Vec<Node> = serde_json::from_str(&pretty.inner().get_output()).unwrap();
+    assert_eq!(compact, pretty);
+}
+
+#[test]
+fn test_separator_is_only_written_for_plain_output() {
+    let plain = BufferWriter::new();
+    let json = JsonWriter::new(BufferWriter::new());
+    for writer in [&plain as &dyn OutputWriter, &json] {
+        writer.write_node(0, "Load balancer (a)");
+        writer.write_separator();
+        writer.write_node(0, "Load balancer (b)");
+    }
+    json.finish();
+
+    assert_eq!(
+        plain.get_output(),
+        "-> Load balancer (a)\n\n-> Load balancer (b)\n"
+    );
+    let roots: Vec<Node> = serde_json::from_str(&json.inner().get_output()).unwrap();
+    assert_eq!(roots.len(), 2);
+}
+
+#[test]
+fn test_markdown_separator_starts_a_new_list() {
+    let writer = MarkdownWriter::new(BufferWriter::new());
+    writer.write_node(0, "Load balancer (a)");
+    writer.write_separator();
+    writer.write_node(0, "Load balancer (b)");
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "- Load balancer (a)\n\n- Load balancer (b)\n"
+    );
+}
+
+#[test]
+fn test_indent_writer_scales_each_level() {
+    let mut root = Node::new("Load balancer");
+    let mut listener = Node::new("Listener");
+    listener.children.push(Node::new("Rule"));
+    root.children.push(listener);
+
+    let writer = IndentWriter::new(BufferWriter::new(), 4);
+    root.render(&writer);
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "-> Load balancer\n    -> Listener\n        -> Rule\n"
+    );
+}
+
+#[test]
+fn test_plain_writer_keeps_only_indentation() {
+    let mut root = Node::new("Load balancer");
+    let mut listener = Node::new("Listener");
+    listener.children.push(Node::new("Rule"));
+    root.children.push(listener);
+
+    let writer = PlainWriter::new(BufferWriter::new(), 2);
+    root.render(&writer);
+    assert_eq!(
+        writer.inner().get_output(),
+        "Load balancer\n  Listener\n    Rule\n"
+    );
+
+    let writer = PlainWriter::new(BufferWriter::new(), 4).marker("* ");
+    root.render(&writer);
+    assert_eq!(
+        writer.inner().get_output(),
+        "* Load balancer\n    * Listener\n        * Rule\n"
+    );
+}
+
+#[test]
+fn test_show_arns_writer_appends_arn() {
+    let mut root = Node::new("Load balancer");
+    root.arn = Some(
+        "arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/a/1".to_string(),
+    );
+    root.children.push(Node::new("Listener"));
+
+    let writer = ShowArnsWriter::new(BufferWriter::new());
+    root.render(&writer);
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "-> Load balancer (arn:aws:elasticloadbalancing:us-east-1:000000000000:loadbalancer/app/a/1)\n  -> Listener\n"
+    );
+}
+
+#[test]
+fn test_max_depth_writer_drops_deeper_resources() {
+    let mut root = Node::new("Load balancer");
+    let mut listener = Node::new("Listener");
+    listener.children.push(Node::new("Rule"));
+    root.children.push(listener);
+
+    let writer = MaxDepthWriter::new(BufferWriter::new(), 1);
+    root.render(&writer);
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "-> Load balancer\n  -> Listener\n"
+    );
+}
+
+#[test]
+fn test_max_items_writer_truncates_each_level() {
+    let writer = MaxItemsWriter::new(BufferWriter::new(), 2);
+    for lb in ["a", "b", "c"] {
+        let mut root = Node::new(format!("Load balancer {lb}"));
+        for port in [80, 443, 8080] {
+            let mut listener = Node::new(format!("Listener port={port}"));
+            listener.children.push(Node::new("Rule"));
+            root.children.push(listener);
+        }
+        root.render(&writer);
+        writer.write_separator();
+    }
+    writer.flush();
+
+    assert_eq!(
+        writer.inner().get_output(),
+        "-> Load balancer a\n  -> Listener port=80\n    -> Rule\n  -> Listener port=443\n    -> Rule\n  -> ... (truncated, 1 more)\n\n\
+         -> Load balancer b\n  -> Listener port=80\n    -> Rule\n  -> Listener port=443\n    -> Rule\n  -> ... (truncated, 1 more)\n\n\
+         -> ... (truncated, 1 more)\n"
+    );
+}
+
+#[test]
+fn test_io_writer_writes_to_any_sink() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<IoWriter<Vec<u8>>>();
+
+    let mut root = Node::new("Load balancer");
+    root.children.push(Node::new("Listener"));
+
+    let writer = IoWriter::new(Vec::new());
+    root.render(&writer);
+    writer.flush();
+
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "-> Load balancer\n  -> Listener\n"
+    );
+}
+
+#[test]
+fn test_tee_writer_writes_to_every_file() {
+    let dir = std::env::temp_dir();
+    let paths = [
+        dir.join(format!("lbtree-tee-{}.txt", uuid::Uuid::new_v4())),
+        dir.join(format!("lbtree-tee-{}.txt", uuid::Uuid::new_v4())),
+    ];
+
+    let mut root = Node::new("Load balancer");
+    root.children.push(Node::new("Listener"));
+
+    let writer = IndentWriter::new(
+        TeeWriter::new(
+            paths
+                .iter()
+                .map(|path| Box::new(FileWriter::create(path).unwrap()) as Box<dyn OutputWriter>)
+                .collect(),
+        ),
+        4,
+    );
+    root.render(&writer);
+    writer.flush();
+
+    for path in &paths {
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "-> Load balancer\n    -> Listener\n"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_prefix_writer_labels_every_line() {
+    let mut root = Node::new("Load balancer");
+    root.children.push(Node::new("Listener"));
+
+    let writer = IndentWriter::new(PrefixWriter::new(BufferWriter::new(), "prod/eu-west-1"), 2);
+    root.render(&writer);
+    writer.write_separator();
+    root.render(&writer);
+
+    assert_eq!(
+        writer.inner().inner().get_output(),
+        "[prod/eu-west-1] -> Load balancer\n[prod/eu-west-1]   -> Listener\n\n\
+         [prod/eu-west-1] -> Load balancer\n[prod/eu-west-1]   -> Listener\n"
+    );
+}